@@ -0,0 +1,381 @@
+use crate::model::{AuditReport, Status};
+use crate::report::escape_xml as esc;
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Fleet view over many host reports, see `vps-audit aggregate`.
+#[derive(Debug, Serialize)]
+pub struct FleetReport {
+    pub generated_at: String,
+    /// Hosts ordered by risk: lowest score first, then most failures
+    pub hosts: Vec<HostEntry>,
+    pub score_distribution: ScoreDistribution,
+    /// Checks failing or warning on at least one host, most widespread first
+    pub failing_checks: Vec<FailingCheck>,
+    pub outliers: Vec<Outlier>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HostEntry {
+    pub name: String,
+    pub source: PathBuf,
+    pub generated_at: String,
+    pub score: u32,
+    pub pass: usize,
+    pub warn: usize,
+    pub fail: usize,
+    pub skip: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreDistribution {
+    pub min: u32,
+    pub max: u32,
+    pub mean: f64,
+    pub median: f64,
+    pub buckets: Vec<ScoreBucket>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoreBucket {
+    pub range: &'static str,
+    pub hosts: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailingCheck {
+    pub id: String,
+    pub title: String,
+    pub fail_hosts: Vec<String>,
+    pub warn_hosts: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Outlier {
+    pub host: String,
+    /// Check id or metric name
+    pub subject: String,
+    pub detail: String,
+}
+
+/// Minimum number of hosts before a host is compared against its peers.
+const MIN_PEERS: usize = 3;
+
+/// Loads reports from `paths`; patterns such as `reports/*.json` are expanded when the shell did not.
+/// Unreadable files are reported on stderr and skipped.
+pub fn load_reports(paths: &[PathBuf]) -> Vec<(PathBuf, AuditReport)> {
+    let mut files = Vec::new();
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        if !path.exists() && pattern.contains(['*', '?', '[']) {
+            match glob::glob(&pattern) {
+                Ok(matches) => files.extend(matches.filter_map(Result::ok)),
+                Err(e) => eprintln!("warning: invalid pattern {}: {}", pattern, e),
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    let mut reports = Vec::new();
+    for file in files {
+        match read_report(&file) {
+            Ok(report) => reports.push((file, report)),
+            Err(e) => eprintln!("warning: skipping {}: {:#}", file.display(), e),
+        }
+    }
+    reports
+}
+
+fn read_report(path: &Path) -> anyhow::Result<AuditReport> {
+    let content = std::fs::read_to_string(path)?;
+    serde_json::from_str(&content).context("not a vps-audit JSON report (generate with --format json)")
+}
+
+impl FleetReport {
+    pub fn build(reports: &[(PathBuf, AuditReport)]) -> Self {
+        let names = host_names(reports);
+
+        let mut hosts: Vec<HostEntry> = reports
+            .iter()
+            .zip(&names)
+            .map(|((source, r), name)| HostEntry {
+                name: name.clone(),
+                source: source.clone(),
+                generated_at: r.generated_at.clone(),
+                score: r.summary.score,
+                pass: r.summary.pass,
+                warn: r.summary.warn,
+                fail: r.summary.fail,
+                skip: r.summary.skip,
+            })
+            .collect();
+        hosts.sort_by(|a, b| a.score.cmp(&b.score).then(b.fail.cmp(&a.fail)).then(a.name.cmp(&b.name)));
+
+        let mut checks: BTreeMap<&str, FailingCheck> = BTreeMap::new();
+        for ((_, r), name) in reports.iter().zip(&names) {
+            for result in r.results.iter().filter(|c| matches!(c.status, Status::Fail | Status::Warn)) {
+                let entry = checks.entry(&result.id).or_insert_with(|| FailingCheck {
+                    id: result.id.clone(),
+                    title: result.title.clone(),
+                    fail_hosts: Vec::new(),
+                    warn_hosts: Vec::new(),
+                });
+                if result.status.is_fail() { entry.fail_hosts.push(name.clone()) } else { entry.warn_hosts.push(name.clone()) }
+            }
+        }
+        let mut failing_checks: Vec<FailingCheck> = checks.into_values().collect();
+        failing_checks.sort_by(|a, b| (b.fail_hosts.len(), b.warn_hosts.len()).cmp(&(a.fail_hosts.len(), a.warn_hosts.len())).then(a.id.cmp(&b.id)));
+
+        let mut outliers = check_outliers(reports, &names);
+        outliers.extend(metric_outliers(reports, &names));
+
+        Self {
+            generated_at: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            score_distribution: score_distribution(&hosts),
+            hosts,
+            failing_checks,
+            outliers,
+        }
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut s = String::new();
+        let d = &self.score_distribution;
+        let _ = writeln!(s, "VPS Audit Fleet Overview\n========================");
+        let _ = writeln!(s, "Hosts: {}", self.hosts.len());
+        let _ = writeln!(s, "Score: min {}, median {:.0}, mean {:.1}, max {}", d.min, d.median, d.mean, d.max);
+        let buckets: Vec<String> = d.buckets.iter().map(|b| format!("{}: {}", b.range, b.hosts)).collect();
+        let _ = writeln!(s, "Distribution: {}\n", buckets.join(", "));
+
+        let _ = writeln!(s, "Hosts by risk:");
+        for h in &self.hosts {
+            let _ = writeln!(s, "  {:>3}  {:<30} FAIL={} WARN={}  ({})", h.score, h.name, h.fail, h.warn, h.generated_at);
+        }
+        let _ = writeln!(s, "\nMost common failing checks:");
+        for c in &self.failing_checks {
+            let _ = writeln!(s, "  {:<28} FAIL on {}, WARN on {}  {}", c.id, c.fail_hosts.len(), c.warn_hosts.len(), c.title);
+        }
+        if self.failing_checks.is_empty() { let _ = writeln!(s, "  none"); }
+        let _ = writeln!(s, "\nOutliers:");
+        for o in &self.outliers {
+            let _ = writeln!(s, "  {}: {}: {}", o.host, o.subject, o.detail);
+        }
+        if self.outliers.is_empty() { let _ = writeln!(s, "  none"); }
+        s
+    }
+
+    pub fn render_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn render_html(&self) -> String {
+        let mut s = String::new();
+        let d = &self.score_distribution;
+        let _ = writeln!(s, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(s, "<title>VPS audit fleet overview</title>\n<style>{}</style>\n</head>\n<body>", crate::report::html::STYLE);
+        let _ = writeln!(s, "<h1>VPS audit fleet overview</h1>\n<p class=\"muted\">{} hosts &middot; {}</p>", self.hosts.len(), esc(&self.generated_at));
+        let _ = writeln!(s, "<h2>Scores</h2>\n<p>min {} &middot; median {:.0} &middot; mean {:.1} &middot; max {}</p>", d.min, d.median, d.mean, d.max);
+        let _ = writeln!(s, "<table>\n<tr><th>Score</th><th>Hosts</th></tr>");
+        for b in &d.buckets { let _ = writeln!(s, "<tr><td>{}</td><td>{}</td></tr>", b.range, b.hosts); }
+        let _ = writeln!(s, "</table>");
+
+        let _ = writeln!(s, "<h2>Hosts by risk</h2>\n<table>\n<tr><th>Host</th><th>Score</th><th>FAIL</th><th>WARN</th><th>Report</th></tr>");
+        for h in &self.hosts {
+            let _ = writeln!(
+                s,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"muted\">{}</td></tr>",
+                esc(&h.name), h.score, h.fail, h.warn, esc(&h.generated_at)
+            );
+        }
+        let _ = writeln!(s, "</table>");
+
+        let _ = writeln!(s, "<h2>Most common failing checks</h2>\n<table>\n<tr><th>Check</th><th>FAIL on</th><th>WARN on</th></tr>");
+        for c in &self.failing_checks {
+            let _ = writeln!(
+                s,
+                "<tr><td>{}<br><code class=\"muted\">{}</code></td><td>{}</td><td>{}</td></tr>",
+                esc(&c.title), esc(&c.id), esc(&c.fail_hosts.join(", ")), esc(&c.warn_hosts.join(", "))
+            );
+        }
+        let _ = writeln!(s, "</table>");
+
+        let _ = writeln!(s, "<h2>Outliers</h2>\n<table>\n<tr><th>Host</th><th>Subject</th><th>Detail</th></tr>");
+        for o in &self.outliers {
+            let _ = writeln!(s, "<tr><td>{}</td><td><code>{}</code></td><td>{}</td></tr>", esc(&o.host), esc(&o.subject), esc(&o.detail));
+        }
+        let _ = writeln!(s, "</table>\n</body>\n</html>");
+        s
+    }
+}
+
+/// Hostname from each report, falling back to the file name, made unique with the file name when hosts collide
+/// (e.g. redacted reports that all say `host-1`).
+fn host_names(reports: &[(PathBuf, AuditReport)]) -> Vec<String> {
+    let base: Vec<String> = reports
+        .iter()
+        .map(|(path, r)| r.host.hostname.clone().unwrap_or_else(|| file_stem(path)))
+        .collect();
+    base.iter()
+        .zip(reports)
+        .map(|(name, (path, _))| {
+            if base.iter().filter(|n| *n == name).count() > 1 { format!("{} ({})", name, file_stem(path)) } else { name.clone() }
+        })
+        .collect()
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| path.display().to_string())
+}
+
+fn score_distribution(hosts: &[HostEntry]) -> ScoreDistribution {
+    let mut scores: Vec<u32> = hosts.iter().map(|h| h.score).collect();
+    scores.sort_unstable();
+    let values: Vec<f64> = scores.iter().map(|&s| s as f64).collect();
+    let bucket = |range: &'static str, lo: u32, hi: u32| ScoreBucket { range, hosts: scores.iter().filter(|&&s| s >= lo && s <= hi).count() };
+    ScoreDistribution {
+        min: scores.first().copied().unwrap_or(0),
+        max: scores.last().copied().unwrap_or(0),
+        mean: if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 },
+        median: median(&values),
+        buckets: vec![bucket("0-49", 0, 49), bucket("50-69", 50, 69), bucket("70-89", 70, 89), bucket("90-100", 90, 100)],
+    }
+}
+
+/// A host failing or warning on a check that at least 75% of its evaluated peers pass.
+fn check_outliers(reports: &[(PathBuf, AuditReport)], names: &[String]) -> Vec<Outlier> {
+    let mut by_check: BTreeMap<&str, Vec<(usize, Status)>> = BTreeMap::new();
+    for (idx, (_, r)) in reports.iter().enumerate() {
+        for result in r.results.iter().filter(|c| !matches!(c.status, Status::Skip)) {
+            by_check.entry(&result.id).or_default().push((idx, result.status));
+        }
+    }
+    let mut outliers = Vec::new();
+    for (id, statuses) in by_check {
+        if statuses.len() < MIN_PEERS { continue; }
+        let passing = statuses.iter().filter(|(_, s)| *s == Status::Pass).count();
+        for &(idx, status) in statuses.iter().filter(|(_, s)| matches!(s, Status::Fail | Status::Warn)) {
+            let peers = statuses.len() - 1;
+            if passing as f64 >= 0.75 * peers as f64 {
+                let reason = reports[idx].1.results.iter().find(|c| c.id == id).map(|c| c.reason.clone()).unwrap_or_default();
+                outliers.push(Outlier {
+                    host: names[idx].clone(),
+                    subject: id.to_string(),
+                    detail: format!("{} while {} of {} other hosts pass: {}", status.as_str(), passing, peers, reason),
+                });
+            }
+        }
+    }
+    outliers
+}
+
+/// Hosts whose raw metrics are far from the fleet median (robust z-score over the median absolute deviation,
+/// or a fixed margin when all peers report the same value).
+fn metric_outliers(reports: &[(PathBuf, AuditReport)], names: &[String]) -> Vec<Outlier> {
+    type Metric = (&'static str, fn(&AuditReport) -> f64, f64);
+    let metrics: [Metric; 5] = [
+        ("listening_ports_total", |r| r.metrics.listening_ports_total as f64, 5.0),
+        ("listening_ports_public", |r| r.metrics.listening_ports_public as f64, 2.0),
+        ("disk_used_percent", |r| r.metrics.disk_used_percent, 25.0),
+        ("memory_used_percent", |r| r.metrics.memory_used_percent, 25.0),
+        ("load_ratio", |r| r.metrics.load_ratio, 0.5),
+    ];
+    let mut outliers = Vec::new();
+    if reports.len() < MIN_PEERS { return outliers; }
+    for (name, value_of, margin) in metrics {
+        let values: Vec<f64> = reports.iter().map(|(_, r)| value_of(r)).collect();
+        let med = median(&values);
+        let deviations: Vec<f64> = values.iter().map(|v| (v - med).abs()).collect();
+        let mad = median(&deviations) * 1.4826;
+        for (idx, value) in values.iter().enumerate() {
+            let diff = value - med;
+            let is_outlier = if mad > 0.0 { diff.abs() / mad > 3.5 && diff.abs() >= margin } else { diff.abs() >= margin };
+            if is_outlier {
+                outliers.push(Outlier {
+                    host: names[idx].clone(),
+                    subject: name.to_string(),
+                    detail: format!("{:.2} vs fleet median {:.2}", value, med),
+                });
+            }
+        }
+    }
+    outliers
+}
+
+fn median(values: &[f64]) -> f64 {
+    if values.is_empty() { return 0.0; }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_report;
+
+    /// One report per host, all from the shared sample with `edit` applied.
+    fn fleet(edits: &[fn(&mut AuditReport)]) -> Vec<(PathBuf, AuditReport)> {
+        edits
+            .iter()
+            .enumerate()
+            .map(|(i, edit)| {
+                let mut report = sample_report();
+                report.host.hostname = Some(format!("web-{}", i + 1));
+                edit(&mut report);
+                (PathBuf::from(format!("web-{}.json", i + 1)), report)
+            })
+            .collect()
+    }
+
+    fn outliers(reports: &[(PathBuf, AuditReport)]) -> Vec<(String, String)> {
+        metric_outliers(reports, &host_names(reports)).into_iter().map(|o| (o.host, o.subject)).collect()
+    }
+
+    #[test]
+    fn metric_far_from_the_median_is_an_outlier() {
+        let reports = fleet(&[
+            |r| r.metrics.disk_used_percent = 40.0,
+            |r| r.metrics.disk_used_percent = 42.0,
+            |r| r.metrics.disk_used_percent = 45.0,
+            |r| r.metrics.disk_used_percent = 41.0,
+            |r| r.metrics.disk_used_percent = 97.0,
+        ]);
+        assert_eq!(outliers(&reports), [("web-5".to_string(), "disk_used_percent".to_string())]);
+    }
+
+    #[test]
+    fn spread_below_the_margin_is_not_an_outlier() {
+        // A robust z-score above 3.5, but only 6 points from the median
+        let reports = fleet(&[
+            |r| r.metrics.disk_used_percent = 40.0,
+            |r| r.metrics.disk_used_percent = 40.5,
+            |r| r.metrics.disk_used_percent = 41.0,
+            |r| r.metrics.disk_used_percent = 47.0,
+        ]);
+        assert!(outliers(&reports).is_empty());
+    }
+
+    #[test]
+    fn identical_peers_fall_back_to_the_margin() {
+        // MAD is zero, so any host at least the margin away stands out
+        let reports = fleet(&[
+            |r| r.metrics.listening_ports_public = 1,
+            |r| r.metrics.listening_ports_public = 1,
+            |r| r.metrics.listening_ports_public = 1,
+            |r| r.metrics.listening_ports_public = 3,
+        ]);
+        assert_eq!(outliers(&reports), [("web-4".to_string(), "listening_ports_public".to_string())]);
+        assert!(outliers(&reports[..2]).is_empty(), "fewer than MIN_PEERS hosts are not compared");
+    }
+
+    #[test]
+    fn median_of_even_and_odd_counts() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), 2.5);
+        assert_eq!(median(&[]), 0.0);
+    }
+}
@@ -0,0 +1,195 @@
+//! `--bundle`: a tar.zst archive with the inputs behind every verdict, for formal audits. It holds the report, the
+//! collected facts, a copy of every host file the audit read, the result of every existence probe, the values read
+//! through system calls (disk usage, CPU count) and every command output (as captured by `trace`), and
+//! `manifest.json` with the SHA-256 hash and collection time of each entry.
+use crate::collectors::Collectors;
+use crate::model::{AuditReport, ToolInfo};
+use crate::output;
+use crate::trace::Capture;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    tool: &'a ToolInfo,
+    hostname: Option<&'a str>,
+    /// When the audit started
+    generated_at: &'a str,
+    entries: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize)]
+struct ManifestEntry {
+    /// Path inside the archive; `null` when the source could not be read
+    path: Option<String>,
+    /// Host path or command line the content came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    /// `report`, `facts`, `file`, `stat` (existence probe), `measurement` (system call), `stdout` or `stderr`
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<usize>,
+    collected_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Writes the bundle atomically with mode 0600. `report_json` is the report exactly as printed (signed, filtered).
+pub fn write(path: &Path, report: &AuditReport, report_json: &str, facts: &Collectors, captures: Vec<Capture>) -> anyhow::Result<()> {
+    let now = SystemTime::now();
+    let facts_json = serde_json::to_string_pretty(facts)?;
+    let mut contents: Vec<(String, Vec<u8>, SystemTime)> = Vec::new();
+    let mut entries = Vec::new();
+    let mut add = |name: String, source: Option<String>, kind: &'static str, data: Vec<u8>, at: SystemTime| {
+        entries.push(ManifestEntry {
+            path: Some(name.clone()),
+            source,
+            kind,
+            sha256: Some(sha256(&data)),
+            size: Some(data.len()),
+            collected_at: humantime::format_rfc3339_millis(at).to_string(),
+            error: None,
+        });
+        contents.push((name, data, at));
+    };
+    add("report.json".into(), None, "report", report_json.as_bytes().to_vec(), now);
+    add("facts.json".into(), None, "facts", facts_json.into_bytes(), now);
+
+    let mut failed = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut names = BTreeSet::new();
+    // A file read twice is kept as first read; checks and collectors see the same content in practice
+    for capture in captures.into_iter().filter(|c| seen.insert((c.kind, c.source.clone()))) {
+        match capture.content {
+            Ok(data) => {
+                let name = match capture.kind {
+                    "file" => archive_path("files", &capture.source, ""),
+                    "stat" => archive_path("stat", &capture.source, ".txt"),
+                    // `statvfs /home` becomes measurements/statvfs/home.txt
+                    "measurement" => archive_path("measurements", &capture.source.replacen(' ', "/", 1), ".txt"),
+                    kind => format!("commands/{}.{}", slug(&capture.source), kind),
+                };
+                // Two sources that normalize to the same path keep their own entries
+                let name = if names.insert(name.clone()) { name } else { format!("{}/{}.{}", capture.kind, slug(&capture.source), names.len()) };
+                add(name, Some(capture.source), capture.kind, data, capture.at);
+            }
+            Err(error) => failed.push(ManifestEntry {
+                path: None,
+                source: Some(capture.source),
+                kind: capture.kind,
+                sha256: None,
+                size: None,
+                collected_at: humantime::format_rfc3339_millis(capture.at).to_string(),
+                error: Some(error),
+            }),
+        }
+    }
+    entries.extend(failed);
+
+    let manifest = Manifest { tool: &report.tool, hostname: report.host.hostname.as_deref(), generated_at: &report.generated_at, entries };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+
+    let mut archive = tar::Builder::new(zstd::Encoder::new(Vec::new(), zstd::DEFAULT_COMPRESSION_LEVEL)?);
+    append(&mut archive, "manifest.json", &manifest_json, now)?;
+    for (name, data, at) in &contents { append(&mut archive, name, data, *at)?; }
+    let compressed = archive.into_inner()?.finish()?;
+    output::write_atomic(path, &compressed)?;
+    Ok(())
+}
+
+fn append(archive: &mut tar::Builder<impl std::io::Write>, name: &str, data: &[u8], at: SystemTime) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default());
+    archive.append_data(&mut header, name, data)
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data).iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{:02x}", b);
+        s
+    })
+}
+
+/// `prefix` plus the host path with `.` and `..` resolved lexically, since tar refuses `..` components (an sshd
+/// `Include ../x.conf`). The manifest keeps the path as read.
+fn archive_path(prefix: &str, host_path: &str, suffix: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in host_path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => { parts.pop(); }
+            part => parts.push(part),
+        }
+    }
+    if parts.is_empty() { parts.push("_root"); }
+    format!("{}/{}{}", prefix, parts.join("/"), suffix)
+}
+
+/// File name for a command line: `/usr/sbin/sshd -T` becomes `usr_sbin_sshd_-T`.
+fn slug(command: &str) -> String {
+    command.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '.', "_").trim_start_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::sample_collectors;
+    use crate::model::sample_report;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io::Read;
+
+    fn capture(source: &str, kind: &'static str, content: Result<&str, &str>) -> Capture {
+        Capture { source: source.into(), kind, content: content.map(|c| c.as_bytes().to_vec()).map_err(String::from), at: SystemTime::now() }
+    }
+
+    #[test]
+    fn manifest_hashes_match_the_archive() {
+        let path = std::env::temp_dir().join(format!("vps-audit-bundle-{}.tar.zst", std::process::id()));
+        let captures = vec![
+            capture("/etc/ssh/sshd_config", "file", Ok("Include ../extra.conf\n")),
+            capture("/etc/ssh/../extra.conf", "file", Ok("PermitRootLogin no\n")),
+            capture("/var/run/reboot-required", "stat", Err("No such file or directory (os error 2)")),
+            capture("statvfs /", "measurement", Ok("fstype=ext4 total_bytes=100 available_bytes=40\n")),
+            capture("/usr/sbin/sshd -T", "stdout", Ok("port 22\n")),
+        ];
+        write(&path, &sample_report(), "{}", &sample_collectors(), captures).unwrap();
+
+        let mut archive = tar::Archive::new(zstd::Decoder::new(fs::File::open(&path).unwrap()).unwrap());
+        let mut files = BTreeMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            files.insert(entry.path().unwrap().display().to_string(), data);
+        }
+        fs::remove_file(&path).unwrap();
+
+        let manifest: serde_json::Value = serde_json::from_slice(&files["manifest.json"]).unwrap();
+        let entries = manifest["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 7);
+        for entry in entries.iter().filter(|e| !e["path"].is_null()) {
+            let name = entry["path"].as_str().unwrap();
+            assert_eq!(entry["sha256"].as_str().unwrap(), sha256(&files[name]), "{}", name);
+        }
+        assert!(files.contains_key("files/etc/extra.conf"));
+        assert!(files.contains_key("measurements/statvfs.txt"));
+        let missing = entries.iter().find(|e| e["kind"] == "stat").unwrap();
+        assert!(missing["path"].is_null() && missing["error"].as_str().unwrap().contains("No such file"));
+    }
+
+    #[test]
+    fn archive_paths_stay_inside_their_prefix() {
+        assert_eq!(archive_path("files", "/etc/ssh/../x.conf", ""), "files/etc/x.conf");
+        assert_eq!(archive_path("files", "/../../etc/./passwd", ""), "files/etc/passwd");
+        assert_eq!(archive_path("stat", "/", ".txt"), "stat/_root.txt");
+    }
+}
@@ -5,4 +5,47 @@ pub mod files;
 pub mod network;
 pub mod firewall;
 
+use crate::model::AuditCheck;
 
+/// Every built-in check, in report order.
+pub fn all() -> Vec<Box<dyn AuditCheck>> {
+    vec![
+        Box::new(ssh::SshRootLoginCheck),
+        Box::new(ssh::SshPasswordAuthCheck),
+        Box::new(ssh::SshPortCheck),
+        Box::new(system::RebootRequiredCheck),
+        Box::new(system::DiskUsageCheck),
+        Box::new(system::MemoryUsageCheck),
+        Box::new(system::CpuUsageCheck),
+        Box::new(policy::SudoLoggingCheck),
+        Box::new(policy::PasswordPolicyCheck),
+        Box::new(files::SuidFilesCheck),
+        Box::new(network::ListeningPortsCheck),
+        Box::new(firewall::FirewallPresenceCheck),
+        Box::new(firewall::NftablesRulesCheck),
+    ]
+}
+
+pub fn find(id: &str) -> Option<Box<dyn AuditCheck>> {
+    all().into_iter().find(|c| c.id() == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn every_check_has_a_unique_id_and_documentation() {
+        let checks = all();
+        let ids: BTreeSet<&str> = checks.iter().map(|c| c.id()).collect();
+        assert_eq!(ids.len(), checks.len());
+        for check in &checks {
+            let meta = check.metadata();
+            assert!(!meta.rationale.is_empty() && !meta.impact.is_empty() && !meta.expected.is_empty(), "{} lacks metadata", check.id());
+            assert!(!meta.references.is_empty(), "{} has no references", check.id());
+            assert_eq!(find(check.id()).map(|c| c.title()), Some(check.title()));
+        }
+        assert!(find("ssh.nonexistent").is_none());
+    }
+}
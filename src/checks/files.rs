@@ -1,7 +1,7 @@
 use crate::collectors::Collectors;
-use crate::model::{AuditCheck, CheckResult, Status};
-use walkdir::WalkDir;
-use std::time::{Duration, Instant};
+use crate::compliance::Framework;
+use crate::model::evidence::{FileModeEvidence, FilesystemEvidence, SuidScanEvidence};
+use crate::model::{AuditCheck, CheckMetadata, CheckResult, Evidence, Finding, Status};
 
 pub struct SuidFilesCheck;
 
@@ -9,12 +9,20 @@ impl AuditCheck for SuidFilesCheck {
     fn id(&self) -> &'static str { "files.suid_suspicious" }
     fn title(&self) -> &'static str { "No suspicious SUID files exist outside standard locations" }
     fn categories(&self) -> &'static [&'static str] { &["security", "linux"] }
-    fn run(&self, _ctx: &Collectors) -> CheckResult {
-        let mut suspicious_count = 0usize;
-        let mut visited_files = 0usize;
-        let start_time = Instant::now();
-        let budget = Duration::from_secs(5);
-        let max_files = 100_000usize;
+    fn controls(&self) -> &'static [(Framework, &'static str)] { &[(Framework::Cis, "6.1.13"), (Framework::Nist80053, "AC-6"), (Framework::Iso27001, "A.8.2")] }
+    fn metadata(&self) -> CheckMetadata {
+        CheckMetadata {
+            rationale: "SUID binaries run with their owner's privileges; unexpected ones outside the standard locations are a classic privilege escalation or backdoor.",
+            impact: "Removing the SUID bit from a legitimate tool breaks it for regular users; verify the package owner (dpkg -S) first.",
+            expected: "No SUID files outside /bin, /sbin, /usr/bin, /usr/sbin and /usr/lib, and a complete scan of all local filesystems",
+            references: &["https://gtfobins.github.io/#+suid"],
+        }
+    }
+    fn uses_file_index(&self) -> bool { true }
+    fn run(&self, ctx: &Collectors) -> CheckResult {
+        let Some(index) = ctx.files.as_deref() else {
+            return CheckResult { reason: "Filesystem index was not collected".into(), ..CheckResult::for_check(self) };
+        };
         let allowed_prefixes = [
             "/usr/bin",
             "/bin",
@@ -23,56 +31,35 @@ impl AuditCheck for SuidFilesCheck {
             "/usr/lib",
             "/usr/libexec",
         ];
-        let ignored_prefixes = [
-            "/proc",
-            "/sys",
-            "/dev",
-            "/run",
-            "/var/lib/docker",
-            "/var/lib/containers",
-            "/snap",
-            "/boot/efi",
-            "/mnt",
-            "/media",
-            "/tmp",
-        ];
 
-        for entry in WalkDir::new("/")
-            .follow_links(false)
-            .into_iter()
-            .filter_map(Result::ok)
-        {
-            if !entry.file_type().is_file() { continue; }
-            let path = entry.path();
-            let p = path.to_string_lossy();
-            if ignored_prefixes.iter().any(|pref| p.starts_with(pref)) { continue; }
-            visited_files += 1;
-            if visited_files >= max_files || start_time.elapsed() > budget { break; }
-            if let Ok(meta) = entry.metadata() {
-                // Check suid bit (04000)
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-                    if meta.permissions().mode() & 0o4000 != 0 {
-                        let is_allowed = allowed_prefixes.iter().any(|pref| p.starts_with(pref));
-                        let is_known = is_known_suid_binary(&p);
-                        if !(is_allowed || is_known) {
-                            suspicious_count += 1;
-                        }
-                    }
-                }
-            }
+        let mut findings = Vec::new();
+        for file in index.files().filter(|f| f.permissions() & 0o4000 != 0) {
+            let p = file.path.to_string_lossy();
+            let is_allowed = allowed_prefixes.iter().any(|pref| p.starts_with(pref));
+            if is_allowed || is_known_suid_binary(&p) { continue; }
+            let mut finding = Finding::new(p.as_ref(), Status::Warn, "SUID bit set outside standard locations");
+            finding.evidence = Some(Evidence::FileMode(FileModeEvidence { mode: format!("{:o}", file.permissions()), uid: file.uid, gid: file.gid }));
+            findings.push(finding);
         }
-        let timed_out = start_time.elapsed() > budget || visited_files >= max_files;
-        let status = if suspicious_count == 0 && !timed_out { Status::Pass } else { Status::Warn };
-        let reason = if suspicious_count == 0 && !timed_out {
+        let visited_files = index.files().count();
+        let unreadable_dirs = index.unreadable.len();
+        let suspicious_count = findings.len();
+        let derived = Status::worst(findings.iter().map(|f| f.status));
+        // An incomplete scan cannot pass even when the part that was scanned is clean
+        let status = if index.complete() { derived } else { Status::worst([derived, Status::Warn]) };
+        let mut reason = if index.timed_out {
+            format!("Partial scan ({} files in {}s) found {} potential suspicious SUID files", visited_files, index.duration_ms / 1000, suspicious_count)
+        } else if suspicious_count == 0 {
             "No suspicious SUID files found".into()
-        } else if timed_out {
-            format!("Partial scan ({} files, ~{}s budget) found {} potential suspicious SUID files", visited_files, budget.as_secs(), suspicious_count)
         } else {
             format!("Found {} potential suspicious SUID files", suspicious_count)
         };
-        CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status, reason, remediation: Some("Investigate SUID files; remove SUID bit if unnecessary".into()), evidence: None }
+        if index.cached_entries > 0 { reason.push_str(&format!("; {}% listed fresh, the rest from cached directory listings", index.fresh_percent())); }
+        if unreadable_dirs > 0 { reason.push_str(&format!("; {} unreadable director{} skipped", unreadable_dirs, if unreadable_dirs == 1 { "y" } else { "ies" })); }
+        let needs_root = (unreadable_dirs > 0 && !ctx.privileges.can_read_all()).then(|| format!("scans {} directories the current user cannot read", unreadable_dirs));
+        let filesystems = index.walked.iter().map(|m| FilesystemEvidence::new(m, true)).chain(index.skipped.iter().map(|m| FilesystemEvidence::new(m, false))).collect();
+        let evidence = Evidence::SuidScan(SuidScanEvidence { files_scanned: visited_files, fresh_percent: index.fresh_percent(), filesystems });
+        CheckResult { status, reason, remediation: Some("Investigate SUID files; remove SUID bit if unnecessary".into()), evidence: Some(evidence), findings, needs_root, ..CheckResult::for_check(self) }
     }
 }
 
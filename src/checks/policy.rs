@@ -1,21 +1,58 @@
-use crate::collectors::Collectors;
-use crate::model::{AuditCheck, CheckResult, Status};
-use std::fs;
+use crate::collectors::{Collectors, ConfigLine, PWQUALITY_CONF, SUDOERS};
+use crate::compliance::Framework;
+use crate::model::evidence::{PasswordPolicyEvidence, SudoLoggingEvidence};
+use crate::model::{AuditCheck, CheckMetadata, CheckResult, Evidence, Location, Prerequisite, Status};
 
 pub struct SudoLoggingCheck;
 pub struct PasswordPolicyCheck;
 
+/// Minimum `minlen` the password policy check accepts.
+const MIN_PASSWORD_LENGTH: u32 = 12;
+
+/// Path from a `Defaults` line such as `Defaults logfile="/var/log/sudo.log", log_input`.
+fn logfile(line: &ConfigLine) -> Option<String> {
+    line.value
+        .split(',')
+        .filter_map(|option| option.trim().strip_prefix("logfile"))
+        .find_map(|rest| rest.trim_start().strip_prefix('='))
+        .map(|path| path.trim().trim_matches('"').to_string())
+}
+
 impl AuditCheck for SudoLoggingCheck {
     fn id(&self) -> &'static str { "policy.sudo_logging" }
     fn title(&self) -> &'static str { "Sudo logging is enabled" }
     fn categories(&self) -> &'static [&'static str] { &["security", "linux"] }
-    fn run(&self, _ctx: &Collectors) -> CheckResult {
-        // Parse /etc/sudoers for Defaults logfile=... without invoking visudo
-        let content = fs::read_to_string("/etc/sudoers").unwrap_or_default();
-        let enabled = content.lines().any(|l| l.trim().starts_with("Defaults") && l.contains("logfile"));
-        let status = if enabled { Status::Pass } else { Status::Fail };
-        let reason = if enabled { "Found Defaults logfile in /etc/sudoers".into() } else { "No Defaults logfile directive found in /etc/sudoers".into() };
-        CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status, reason, remediation: Some("Add 'Defaults logfile=/var/log/sudo.log' to /etc/sudoers via visudo".into()), evidence: None }
+    fn controls(&self) -> &'static [(Framework, &'static str)] { &[(Framework::Cis, "5.3.3"), (Framework::Nist80053, "AU-2"), (Framework::Nist80053, "AU-12"), (Framework::Iso27001, "A.8.15"), (Framework::Bsi, "OPS.1.1.5")] }
+    fn metadata(&self) -> CheckMetadata {
+        CheckMetadata {
+            rationale: "A dedicated sudo log records who ran which privileged command, which is essential after an incident.",
+            impact: "The log file grows over time; add it to logrotate.",
+            expected: "Defaults logfile=/var/log/sudo.log in /etc/sudoers",
+            references: &["https://www.sudo.ws/docs/man/sudoers.man/#logfile"],
+        }
+    }
+    fn prerequisites(&self) -> &'static [Prerequisite] { &[Prerequisite::Root] }
+    fn run(&self, ctx: &Collectors) -> CheckResult {
+        // Parsed sudoers (with includes) for Defaults logfile=... without invoking visudo
+        let directive = ctx.sudoers.iter().find(|l| l.key == "defaults" && l.value.contains("logfile") && !l.value.contains("!logfile"));
+        let status = if directive.is_some() { Status::Pass } else { Status::Fail };
+        let evidence = Evidence::SudoLogging(SudoLoggingEvidence {
+            logfile: directive.and_then(logfile),
+            source: directive.map(ConfigLine::location),
+            lines_read: ctx.sudoers.len(),
+        });
+        let (reason, location) = match directive {
+            Some(line) => (format!("Found Defaults logfile in {}", line.path), line.location()),
+            None => (format!("No Defaults logfile directive found in {} or its includes", SUDOERS), Location::file(SUDOERS)),
+        };
+        CheckResult {
+            status,
+            reason,
+            remediation: Some("Add 'Defaults logfile=/var/log/sudo.log' to /etc/sudoers via visudo".into()),
+            evidence: Some(evidence),
+            locations: vec![location],
+            ..CheckResult::for_check(self)
+        }
     }
 }
 
@@ -23,22 +60,54 @@ impl AuditCheck for PasswordPolicyCheck {
     fn id(&self) -> &'static str { "policy.password_policy" }
     fn title(&self) -> &'static str { "Strong password policy is enforced" }
     fn categories(&self) -> &'static [&'static str] { &["security", "linux"] }
-    fn run(&self, _ctx: &Collectors) -> CheckResult {
-        let content = fs::read_to_string("/etc/security/pwquality.conf").unwrap_or_default();
-        // Check for minlen >= 12 as in the bash script idea
-        let mut minlen_ok = false;
-        for line in content.lines() {
-            let line = line.split('#').next().unwrap_or("").trim();
-            if line.starts_with("minlen") {
-                if let Some((_, v)) = line.split_once('=') {
-                    if v.trim().parse::<u32>().unwrap_or(0) >= 12 { minlen_ok = true; }
-                }
-            }
+    fn controls(&self) -> &'static [(Framework, &'static str)] { &[(Framework::Cis, "5.4.1"), (Framework::Nist80053, "IA-5(1)"), (Framework::Iso27001, "A.5.17"), (Framework::Bsi, "ORP.4.A8")] }
+    fn metadata(&self) -> CheckMetadata {
+        CheckMetadata {
+            rationale: "Short passwords fall quickly to offline cracking and online guessing, including for sudo prompts.",
+            impact: "Only applies to passwords set after the change; existing weak passwords stay valid until users change them.",
+            expected: "minlen >= 12 in /etc/security/pwquality.conf",
+            references: &["https://manpages.debian.org/pwquality.conf", "https://pages.nist.gov/800-63-3/sp800-63b.html"],
         }
+    }
+    fn run(&self, ctx: &Collectors) -> CheckResult {
+        // Check for minlen >= 12 as in the bash script idea; the last setting read wins
+        let minlen = ctx.pwquality.iter().rfind(|l| l.key == "minlen");
+        let minlen_ok = minlen.is_some_and(|l| l.value.parse::<u32>().unwrap_or(0) >= MIN_PASSWORD_LENGTH);
         let status = if minlen_ok { Status::Pass } else { Status::Fail };
-        let reason = if minlen_ok { "minlen >= 12 configured".into() } else { "minlen < 12 or no policy configured".into() };
-        CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status, reason, remediation: Some("Configure /etc/security/pwquality.conf with 'minlen=12' or higher".into()), evidence: None }
+        let reason = match minlen {
+            Some(line) if minlen_ok => format!("minlen = {} configured", line.value),
+            Some(line) => format!("minlen = {} (should be >= 12)", line.value),
+            None => "No minlen configured in pwquality.conf or pwquality.conf.d".into(),
+        };
+        let location = minlen.map(|l| l.location()).unwrap_or_else(|| Location::file(PWQUALITY_CONF));
+        let evidence = Evidence::PasswordPolicy(PasswordPolicyEvidence {
+            minlen: minlen.map(|l| l.value.clone()),
+            required: MIN_PASSWORD_LENGTH,
+            source: minlen.map(ConfigLine::location),
+        });
+        CheckResult {
+            status,
+            reason,
+            remediation: Some("Configure /etc/security/pwquality.conf with 'minlen=12' or higher".into()),
+            evidence: Some(evidence),
+            locations: vec![location],
+            ..CheckResult::for_check(self)
+        }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults(value: &str) -> ConfigLine {
+        ConfigLine { path: SUDOERS.into(), line: 1, key: "defaults".into(), value: value.into(), text: format!("Defaults {}", value), conditional: false }
+    }
 
+    #[test]
+    fn logfile_is_read_from_defaults_options() {
+        assert_eq!(logfile(&defaults("logfile=/var/log/sudo.log")).as_deref(), Some("/var/log/sudo.log"));
+        assert_eq!(logfile(&defaults("use_pty, logfile = \"/var/log/sudo.log\", log_input")).as_deref(), Some("/var/log/sudo.log"));
+        assert_eq!(logfile(&defaults("env_reset")), None);
+    }
+}
@@ -1,45 +1,54 @@
-use crate::collectors::Collectors;
-use crate::model::{AuditCheck, CheckResult, Status};
+use crate::collectors::{Collectors, SshdConfigDump, SSHD_CONFIG};
+use crate::compliance::Framework;
+use crate::model::evidence::SshdSettingEvidence;
+use crate::model::{AuditCheck, CheckMetadata, CheckResult, Evidence, Prerequisite, Status};
 
 pub struct SshRootLoginCheck;
 pub struct SshPasswordAuthCheck;
 pub struct SshPortCheck;
 
+/// Evidence for a result based on `setting`: its value and how the configuration was obtained.
+fn setting_evidence(sshd: &SshdConfigDump, setting: &str, value: impl ToString) -> Evidence {
+    Evidence::SshdSetting(SshdSettingEvidence {
+        setting: setting.into(),
+        value: value.to_string(),
+        source: if sshd.uses_sshd_output() { "sshd -T".into() } else { SSHD_CONFIG.into() },
+        command: sshd.invocation.clone(),
+    })
+}
+
 impl AuditCheck for SshRootLoginCheck {
     fn id(&self) -> &'static str { "ssh.root_login" }
     fn title(&self) -> &'static str { "SSH root login is disabled" }
     fn categories(&self) -> &'static [&'static str] { &["security", "linux", "config"] }
+    fn controls(&self) -> &'static [(Framework, &'static str)] { &[(Framework::Cis, "5.2.7"), (Framework::Nist80053, "AC-6(2)"), (Framework::Nist80053, "AC-17"), (Framework::Iso27001, "A.8.2"), (Framework::Bsi, "SYS.1.3.A8")] }
+    fn metadata(&self) -> CheckMetadata {
+        CheckMetadata {
+            rationale: "Direct root logins give an attacker full control with a single guessed or stolen credential and leave no trace of which administrator logged in.",
+            impact: "Make sure a non-root account with sudo rights and a working key can log in before reloading sshd, or you lock yourself out.",
+            expected: "PermitRootLogin no (or prohibit-password when root must log in with keys only)",
+            references: &["https://man.openbsd.org/sshd_config#PermitRootLogin", "https://www.cisecurity.org/benchmark/ubuntu_linux"],
+        }
+    }
+    fn prerequisites(&self) -> &'static [Prerequisite] { &[Prerequisite::SshdConfig] }
     fn run(&self, ctx: &Collectors) -> CheckResult {
-        if let Some(sshd) = &ctx.sshd {
-            if !sshd.ok {
-                return CheckResult {
-                    id: self.id().to_string(),
-                    title: self.title().to_string(),
-                    categories: self.categories().iter().map(|s| s.to_string()).collect(),
-                    status: Status::Skip,
-                    reason: format!("Unable to obtain sshd config: {}", sshd.stderr.clone().unwrap_or_default()),
-                    remediation: Some("Ensure OpenSSH server is installed and accessible".into()),
-                    evidence: None,
-                };
-            }
-            let value = sshd.values.get("permitrootlogin").cloned().unwrap_or_else(|| "prohibit-password".to_string());
-            let status = if value == "no" || value == "prohibit-password" { Status::Pass } else { Status::Fail };
-            let reason = match status {
-                Status::Pass => format!("PermitRootLogin is '{}'", value),
-                Status::Fail => format!("PermitRootLogin is '{}' (should be 'no' or 'prohibit-password')", value),
-                _ => unreachable!(),
-            };
-            return CheckResult {
-                id: self.id().to_string(),
-                title: self.title().to_string(),
-                categories: self.categories().iter().map(|s| s.to_string()).collect(),
-                status,
-                reason,
-                remediation: Some("Edit sshd_config to set PermitRootLogin no or prohibit-password; then systemctl reload sshd".into()),
-                evidence: Some(serde_json::json!({"permitrootlogin": value})),
-            };
+        let sshd = ctx.sshd_config();
+        let value = sshd.values.get("permitrootlogin").cloned().unwrap_or_else(|| "prohibit-password".to_string());
+        let status = if value == "no" || value == "prohibit-password" { Status::Pass } else { Status::Fail };
+        let reason = match status {
+            Status::Pass => format!("PermitRootLogin is '{}'", value),
+            Status::Fail => format!("PermitRootLogin is '{}' (should be 'no' or 'prohibit-password')", value),
+            _ => unreachable!(),
+        };
+        CheckResult {
+            status,
+            reason,
+            remediation: Some("Edit sshd_config to set PermitRootLogin no or prohibit-password; then systemctl reload sshd".into()),
+            evidence: Some(setting_evidence(sshd, "permitrootlogin", &value)),
+            locations: vec![sshd.location_or_main("permitrootlogin")],
+            needs_root: sshd.root_note(),
+            ..CheckResult::for_check(self)
         }
-        CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status: Status::Skip, reason: "OpenSSH server configuration not found".into(), remediation: None, evidence: None }
     }
 }
 
@@ -47,21 +56,26 @@ impl AuditCheck for SshPasswordAuthCheck {
     fn id(&self) -> &'static str { "ssh.password_auth" }
     fn title(&self) -> &'static str { "SSH password authentication is disabled" }
     fn categories(&self) -> &'static [&'static str] { &["security", "linux", "config"] }
-    fn run(&self, ctx: &Collectors) -> CheckResult {
-        if let Some(sshd) = &ctx.sshd {
-            if !sshd.ok {
-                return CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status: Status::Skip, reason: "Unable to obtain sshd config".into(), remediation: None, evidence: None };
-            }
-            let value = sshd.values.get("passwordauthentication").cloned().unwrap_or_else(|| "yes".to_string());
-            let status = if value == "no" { Status::Pass } else { Status::Fail };
-            let reason = match status {
-                Status::Pass => "PasswordAuthentication is 'no'".to_string(),
-                Status::Fail => format!("PasswordAuthentication is '{}' (should be 'no')", value),
-                _ => unreachable!(),
-            };
-            return CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status, reason, remediation: Some("Set PasswordAuthentication no; enforce key-based auth".into()), evidence: Some(serde_json::json!({"passwordauthentication": value})) };
+    fn controls(&self) -> &'static [(Framework, &'static str)] { &[(Framework::Nist80053, "IA-2"), (Framework::Nist80053, "AC-17"), (Framework::Iso27001, "A.8.5"), (Framework::Bsi, "SYS.1.3.A8")] }
+    fn metadata(&self) -> CheckMetadata {
+        CheckMetadata {
+            rationale: "Password logins can be brute-forced and phished; public keys cannot be guessed over the network.",
+            impact: "Every user needs an authorized key in ~/.ssh/authorized_keys before the change; keep an existing session open while testing a new login.",
+            expected: "PasswordAuthentication no",
+            references: &["https://man.openbsd.org/sshd_config#PasswordAuthentication"],
         }
-        CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status: Status::Skip, reason: "OpenSSH server configuration not found".into(), remediation: None, evidence: None }
+    }
+    fn prerequisites(&self) -> &'static [Prerequisite] { &[Prerequisite::SshdConfig] }
+    fn run(&self, ctx: &Collectors) -> CheckResult {
+        let sshd = ctx.sshd_config();
+        let value = sshd.values.get("passwordauthentication").cloned().unwrap_or_else(|| "yes".to_string());
+        let status = if value == "no" { Status::Pass } else { Status::Fail };
+        let reason = match status {
+            Status::Pass => "PasswordAuthentication is 'no'".to_string(),
+            Status::Fail => format!("PasswordAuthentication is '{}' (should be 'no')", value),
+            _ => unreachable!(),
+        };
+        CheckResult { status, reason, remediation: Some("Set PasswordAuthentication no; enforce key-based auth".into()), evidence: Some(setting_evidence(sshd, "passwordauthentication", &value)), locations: vec![sshd.location_or_main("passwordauthentication")], needs_root: sshd.root_note(), ..CheckResult::for_check(self) }
     }
 }
 
@@ -69,37 +83,42 @@ impl AuditCheck for SshPortCheck {
     fn id(&self) -> &'static str { "ssh.port" }
     fn title(&self) -> &'static str { "SSH uses a non-default and privileged port" }
     fn categories(&self) -> &'static [&'static str] { &["security", "linux", "config"] }
-    fn run(&self, ctx: &Collectors) -> CheckResult {
-        if let Some(sshd) = &ctx.sshd {
-            if !sshd.ok {
-                return CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status: Status::Skip, reason: "Unable to obtain sshd config".into(), remediation: None, evidence: None };
-            }
-            let port_str = sshd.values.get("port").cloned().unwrap_or_else(|| "22".to_string());
-            let port: u16 = port_str.parse().unwrap_or(22);
-            // Linux kernel unprivileged start defaults to 1024; system tunable sometimes at net.ipv4.ip_unprivileged_port_start
-            let unpriv_start = read_unprivileged_start().unwrap_or(1024);
-            let status = if port == 22 {
-                Status::Warn
-            } else if port >= unpriv_start as u16 {
-                Status::Fail
-            } else {
-                Status::Pass
-            };
-            let reason = match status {
-                Status::Pass => format!("Using privileged non-default port {} (< {})", port, unpriv_start),
-                Status::Warn => "Using default SSH port 22".to_string(),
-                Status::Fail => format!("Using unprivileged port {} (>= {})", port, unpriv_start),
-                _ => unreachable!(),
-            };
-            return CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status, reason, remediation: Some(format!("Choose a port < {} and not 22; update sshd_config and reload", unpriv_start)), evidence: Some(serde_json::json!({"port": port})) };
+    fn controls(&self) -> &'static [(Framework, &'static str)] { &[(Framework::Nist80053, "CM-6"), (Framework::Iso27001, "A.8.9")] }
+    fn metadata(&self) -> CheckMetadata {
+        CheckMetadata {
+            rationale: "Moving SSH off port 22 cuts the noise of automated scanners, and a port below 1024 cannot be bound by unprivileged users if sshd stops.",
+            impact: "Firewall rules, SELinux port labels, monitoring and every client config (~/.ssh/config, automation) must follow the new port.",
+            expected: "Port below 1024 and not 22",
+            references: &["https://man.openbsd.org/sshd_config#Port"],
         }
-        CheckResult { id: self.id().to_string(), title: self.title().to_string(), categories: self.categories().iter().map(|s| s.to_string()).collect(), status: Status::Skip, reason: "OpenSSH server configuration not found".into(), remediation: None, evidence: None }
+    }
+    fn prerequisites(&self) -> &'static [Prerequisite] { &[Prerequisite::SshdConfig] }
+    fn run(&self, ctx: &Collectors) -> CheckResult {
+        let sshd = ctx.sshd_config();
+        let port_str = sshd.values.get("port").cloned().unwrap_or_else(|| "22".to_string());
+        let port: u16 = port_str.parse().unwrap_or(22);
+        // Linux kernel unprivileged start defaults to 1024; system tunable sometimes at net.ipv4.ip_unprivileged_port_start
+        let unpriv_start = read_unprivileged_start().unwrap_or(1024);
+        let status = if port == 22 {
+            Status::Warn
+        } else if port >= unpriv_start as u16 {
+            Status::Fail
+        } else {
+            Status::Pass
+        };
+        let reason = match status {
+            Status::Pass => format!("Using privileged non-default port {} (< {})", port, unpriv_start),
+            Status::Warn => "Using default SSH port 22".to_string(),
+            Status::Fail => format!("Using unprivileged port {} (>= {})", port, unpriv_start),
+            _ => unreachable!(),
+        };
+        CheckResult { status, reason, remediation: Some(format!("Choose a port < {} and not 22; update sshd_config and reload", unpriv_start)), evidence: Some(setting_evidence(sshd, "port", port)), locations: vec![sshd.location_or_main("port")], needs_root: sshd.root_note(), ..CheckResult::for_check(self) }
     }
 }
 
 fn read_unprivileged_start() -> Option<u32> {
     // Read from procfs if available to avoid external binary dependency
-    if let Ok(s) = std::fs::read_to_string("/proc/sys/net/ipv4/ip_unprivileged_port_start") {
+    if let Ok(s) = crate::trace::read_to_string("/proc/sys/net/ipv4/ip_unprivileged_port_start") {
         return s.trim().parse::<u32>().ok();
     }
     // Fallback default
@@ -1,10 +1,17 @@
-use serde::Serialize;
-use std::collections::BTreeMap;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::Path;
+use crate::model::{Location, StepTiming};
+use crate::exec::{self, Invocation};
+use crate::fsindex::FileIndex;
+use crate::trace;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 use sysinfo::{Disks, System};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SystemInfo {
     pub hostname: Option<String>,
     pub os_pretty_name: Option<String>,
@@ -13,12 +20,59 @@ pub struct SystemInfo {
     pub total_memory_bytes: u64,
     pub total_swap_bytes: u64,
     pub load_average_1m: Option<f64>,
+    pub cpu_cores: usize,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct DiskInfo {
     pub total_bytes: u64,
     pub available_bytes: u64,
+    /// Each mounted disk the totals are summed over
+    pub mounts: Vec<MountUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MountUsage {
+    pub mount_point: String,
+    pub fstype: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl DiskInfo {
+    pub fn used_pct(&self) -> f64 {
+        used_percent(self.total_bytes, self.available_bytes)
+    }
+}
+
+impl MountUsage {
+    pub fn used_pct(&self) -> f64 {
+        used_percent(self.total_bytes, self.available_bytes)
+    }
+}
+
+fn used_percent(total_bytes: u64, available_bytes: u64) -> f64 {
+    let total = total_bytes as f64;
+    if total > 0.0 { (1.0 - (available_bytes as f64 / total)) * 100.0 } else { 0.0 }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryInfo {
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl MemoryInfo {
+    pub fn used_pct(&self) -> f64 {
+        used_percent(self.total_bytes, self.available_bytes)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListeningPort {
+    pub port: u16,
+    pub proto: &'static str,
+    pub public: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -26,19 +80,95 @@ pub struct SshdConfigDump {
     pub ok: bool,
     pub values: BTreeMap<String, String>,
     pub stderr: Option<String>,
+    /// Directives as written in sshd_config and its includes, in the order sshd reads them
+    pub sources: Vec<ConfigLine>,
+    /// `sshd -T` failed for lack of privileges and `values` were parsed from the files instead
+    pub needs_root: bool,
+    /// The `sshd -T` run, `None` when the files were parsed because sshd or external commands are unavailable
+    pub invocation: Option<Invocation>,
+}
+
+impl SshdConfigDump {
+    /// Line that sets `key` for all connections. sshd keeps the first value it reads, so that is the one to change.
+    pub fn location(&self, key: &str) -> Option<Location> {
+        self.sources.iter().find(|l| !l.conditional && l.key == key).map(ConfigLine::location)
+    }
+
+    /// `sshd -T` output was used, rather than values parsed from the files.
+    pub fn uses_sshd_output(&self) -> bool {
+        self.invocation.is_some() && !self.needs_root
+    }
+
+    /// What a root run adds when `values` had to be parsed from the files.
+    pub fn root_note(&self) -> Option<String> {
+        self.needs_root.then(|| "reads the effective configuration from sshd -T instead of parsing sshd_config (defaults, Match blocks)".to_string())
+    }
+
+    /// Like [`SshdConfigDump::location`], falling back to the main config file where a missing setting would be added.
+    pub fn location_or_main(&self, key: &str) -> Location {
+        self.location(key).unwrap_or_else(|| Location::file(SSHD_CONFIG))
+    }
+}
+
+/// A directive read from a configuration file, after following includes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigLine {
+    pub path: String,
+    pub line: u32,
+    /// Lowercase keyword, e.g. `permitrootlogin`, `defaults` or `minlen`
+    pub key: String,
+    pub value: String,
+    pub text: String,
+    /// Inside an sshd `Match` block, i.e. only applies to some connections
+    pub conditional: bool,
+}
+
+impl ConfigLine {
+    pub fn location(&self) -> Location {
+        Location { path: self.path.clone(), line: Some(self.line), text: Some(self.text.clone()) }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Collectors {
     pub system: SystemInfo,
     pub disk: DiskInfo,
+    pub memory: MemoryInfo,
+    pub listening_ports: Vec<ListeningPort>,
     pub sshd: Option<SshdConfigDump>,
+    /// /etc/sudoers with `@include`/`@includedir` followed
+    pub sudoers: Vec<ConfigLine>,
+    /// pwquality.conf.d drop-ins followed by pwquality.conf; the last `minlen` wins
+    pub pwquality: Vec<ConfigLine>,
     pub files_exist: BTreeMap<String, bool>,
+    pub privileges: Privileges,
+    /// Container runtime (docker, podman, lxc, ...) when running inside one
+    pub container: Option<String>,
+    /// Metadata of every file on local filesystems, only collected when a selected check uses it
+    #[serde(skip)]
+    pub files: Option<Arc<FileIndex>>,
+    /// How long each collection step took, in collection order
+    pub timings: Vec<StepTiming>,
 }
 
 impl Collectors {
-    pub fn collect() -> Self {
-        let system = System::new_all();
+    /// Collects all facts; the filesystem is only walked when `file_index` is set.
+    pub fn collect(file_index: bool) -> Self {
+        let mut timings = Vec::new();
+        let (system, os_pretty_name) = timed(&mut timings, "system", || {
+            // Parse /etc/os-release PRETTY_NAME
+            let os_pretty_name = trace::read_to_string("/etc/os-release").ok().and_then(|content| {
+                for line in content.lines() {
+                    let line = line.trim();
+                    if let Some(rest) = line.strip_prefix("PRETTY_NAME=") {
+                        let val = rest.trim().trim_matches('"').to_string();
+                        return Some(val);
+                    }
+                }
+                None
+            });
+            (System::new_all(), os_pretty_name)
+        });
 
         let hostname = System::host_name();
         let kernel_version = System::kernel_version();
@@ -46,29 +176,46 @@ impl Collectors {
         let total_memory_bytes = system.total_memory();
         let total_swap_bytes = system.total_swap();
 
-        // Parse /etc/os-release PRETTY_NAME
-        let os_pretty_name = fs::read_to_string("/etc/os-release").ok().and_then(|content| {
-            for line in content.lines() {
-                let line = line.trim();
-                if let Some(rest) = line.strip_prefix("PRETTY_NAME=") {
-                    let val = rest.trim().trim_matches('"').to_string();
-                    return Some(val);
-                }
+        let (total, avail, mounts) = timed(&mut timings, "disk", || {
+            let disks = Disks::new_with_refreshed_list();
+            let mut total = 0u64;
+            let mut avail = 0u64;
+            let mut mounts = Vec::new();
+            for disk in disks.list() {
+                total = total.saturating_add(disk.total_space());
+                avail = avail.saturating_add(disk.available_space());
+                trace::measured(&format!("statvfs {}", disk.mount_point().display()), || {
+                    format!("fstype={} total_bytes={} available_bytes={}", disk.file_system().to_string_lossy(), disk.total_space(), disk.available_space())
+                });
+                mounts.push(MountUsage {
+                    mount_point: disk.mount_point().display().to_string(),
+                    fstype: disk.file_system().to_string_lossy().into_owned(),
+                    total_bytes: disk.total_space(),
+                    available_bytes: disk.available_space(),
+                });
             }
-            None
+            (total, avail, mounts)
         });
 
-        let disks = Disks::new_with_refreshed_list();
-        let mut total = 0u64;
-        let mut avail = 0u64;
-        for disk in disks.list() {
-            total = total.saturating_add(disk.total_space());
-            avail = avail.saturating_add(disk.available_space());
-        }
+        // total_memory_bytes includes caches in sysinfo; we approximate with available from /proc/meminfo when possible
+        let (mem_total, mem_avail) = timed(&mut timings, "memory", read_meminfo).unwrap_or((total_memory_bytes, total_memory_bytes / 2));
 
-        let load_average_1m = read_loadavg();
+        let load_average_1m = timed(&mut timings, "load", read_loadavg);
+        let cpu_cores = num_cpus::get();
+        trace::measured("sched_getaffinity", || format!("cpu_cores={}", cpu_cores));
+        let listening_ports = timed(&mut timings, "listening_ports", collect_listening_ports);
+
+        let privileges = timed(&mut timings, "privileges", Privileges::current);
+        let container = timed(&mut timings, "container", detect_container);
+        let sshd = timed(&mut timings, "sshd", || dump_sshd_config(&privileges));
+        let sudoers = timed(&mut timings, "sudoers", || {
+            let mut sudoers = Vec::new();
+            read_sudoers(Path::new(SUDOERS), 0, &mut sudoers);
+            sudoers
+        });
+        let pwquality = timed(&mut timings, "pwquality", read_pwquality);
 
-        let sshd = dump_sshd_config();
+        let files = file_index.then(|| timed(&mut timings, "filesystem", FileIndex::shared));
 
         let mut files_exist = BTreeMap::new();
         for path in [
@@ -76,7 +223,7 @@ impl Collectors {
             "/etc/sudoers",
             "/etc/security/pwquality.conf",
         ] {
-            files_exist.insert(path.to_string(), Path::new(path).exists());
+            files_exist.insert(path.to_string(), trace::exists(path));
         }
 
         Self {
@@ -88,16 +235,113 @@ impl Collectors {
                 total_memory_bytes,
                 total_swap_bytes,
                 load_average_1m,
+                cpu_cores,
             },
-            disk: DiskInfo { total_bytes: total, available_bytes: avail },
+            disk: DiskInfo { total_bytes: total, available_bytes: avail, mounts },
+            memory: MemoryInfo { total_bytes: mem_total, available_bytes: mem_avail },
+            listening_ports,
             sshd,
+            sudoers,
+            pwquality,
             files_exist,
+            privileges,
+            container,
+            files,
+            timings,
+        }
+    }
+}
+
+impl Collectors {
+    /// 1 minute load average per CPU core.
+    pub fn load_ratio(&self) -> f64 {
+        let load1 = self.system.load_average_1m.unwrap_or(0.0);
+        let cores = self.system.cpu_cores as f64;
+        if cores > 0.0 { load1 / cores } else { 0.0 }
+    }
+
+    pub fn public_listening_ports(&self) -> usize {
+        self.listening_ports.iter().filter(|p| p.public).count()
+    }
+
+    /// The readable sshd configuration, for checks declaring [`crate::model::Prerequisite::SshdConfig`]; the engine
+    /// skips them before `run` otherwise.
+    pub fn sshd_config(&self) -> &SshdConfigDump {
+        self.sshd.as_ref().filter(|s| s.ok).expect("sshd config checked by Prerequisite::SshdConfig")
+    }
+}
+
+/// Linux capability names by bit number, see capabilities(7).
+const CAPABILITIES: [&str; 41] = [
+    "cap_chown", "cap_dac_override", "cap_dac_read_search", "cap_fowner", "cap_fsetid", "cap_kill", "cap_setgid",
+    "cap_setuid", "cap_setpcap", "cap_linux_immutable", "cap_net_bind_service", "cap_net_broadcast", "cap_net_admin",
+    "cap_net_raw", "cap_ipc_lock", "cap_ipc_owner", "cap_sys_module", "cap_sys_rawio", "cap_sys_chroot", "cap_sys_ptrace",
+    "cap_sys_pacct", "cap_sys_admin", "cap_sys_boot", "cap_sys_nice", "cap_sys_resource", "cap_sys_time",
+    "cap_sys_tty_config", "cap_mknod", "cap_lease", "cap_audit_write", "cap_audit_control", "cap_setfcap",
+    "cap_mac_override", "cap_mac_admin", "cap_syslog", "cap_wake_alarm", "cap_block_suspend", "cap_audit_read",
+    "cap_perfmon", "cap_bpf", "cap_checkpoint_restore",
+];
+
+/// Effective uid and capabilities of the audit process.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Privileges {
+    pub euid: Option<u32>,
+    /// Effective capabilities, e.g. `cap_dac_read_search`; `null` when /proc/self/status is unavailable
+    pub capabilities: Option<Vec<String>>,
+}
+
+impl Privileges {
+    pub fn current() -> Self {
+        let status = trace::read_to_string("/proc/self/status").unwrap_or_default();
+        let field = |name: &str| status.lines().find_map(|l| l.strip_prefix(name)).map(str::trim);
+        // Uid: real effective saved filesystem
+        let euid = field("Uid:").and_then(|v| v.split_whitespace().nth(1)?.parse().ok());
+        let capabilities = field("CapEff:").and_then(|v| u64::from_str_radix(v, 16).ok()).map(|mask| {
+            CAPABILITIES.iter().enumerate().filter(|(bit, _)| mask & (1 << bit) != 0).map(|(_, name)| name.to_string()).collect()
+        });
+        Self { euid, capabilities }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.euid == Some(0)
+    }
+
+    /// Whether file permissions can be bypassed for reading, which is what root is needed for here.
+    /// Root without CAP_DAC_* (e.g. in a locked-down container) cannot; a non-root user with the capability can.
+    pub fn can_read_all(&self) -> bool {
+        match &self.capabilities {
+            Some(caps) => caps.iter().any(|c| c == "cap_dac_read_search" || c == "cap_dac_override"),
+            None => self.is_root(),
         }
     }
 }
 
+/// Same markers systemd-detect-virt --container looks at, plus the cgroup path for runtimes that set none.
+fn detect_container() -> Option<String> {
+    if let Ok(name) = trace::read_to_string("/run/systemd/container") {
+        if !name.trim().is_empty() { return Some(name.trim().to_string()); }
+    }
+    if trace::exists("/.dockerenv") { return Some("docker".into()); }
+    if trace::exists("/run/.containerenv") { return Some("podman".into()); }
+    let cgroup = trace::read_to_string("/proc/1/cgroup").unwrap_or_default();
+    [("kubepods", "kubernetes"), ("docker", "docker"), ("containerd", "containerd"), ("lxc", "lxc")]
+        .iter()
+        .find(|(marker, _)| cgroup.contains(marker))
+        .map(|(_, name)| name.to_string())
+}
+
+/// Runs one collection step and records how long it took.
+fn timed<T>(timings: &mut Vec<StepTiming>, name: &str, step: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let value = step();
+    let elapsed = start.elapsed();
+    trace::event("collect", || format!("{} in {}", name, trace::duration(elapsed)));
+    timings.push(StepTiming { name: name.to_string(), duration_ms: elapsed.as_millis() as u64 });
+    value
+}
+
 fn read_loadavg() -> Option<f64> {
-    if let Ok(content) = fs::read_to_string("/proc/loadavg") {
+    if let Ok(content) = trace::read_to_string("/proc/loadavg") {
         let mut parts = content.split_whitespace();
         if let Some(first) = parts.next() {
             return first.parse::<f64>().ok();
@@ -106,38 +350,309 @@ fn read_loadavg() -> Option<f64> {
     None
 }
 
-fn dump_sshd_config() -> Option<SshdConfigDump> {
-    // Prefer robust `sshd -T` (effective config). Fallback to parsing file if sshd missing.
-    if let Ok(output) = std::process::Command::new("sshd").arg("-T").output() {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+fn read_meminfo() -> Option<(u64, u64)> {
+    let content = trace::read_to_string("/proc/meminfo").ok()?;
+    let mut total = None;
+    let mut avail = None;
+    for line in content.lines() {
+        if line.starts_with("MemTotal:") {
+            total = parse_kib_line(line);
+        } else if line.starts_with("MemAvailable:") {
+            avail = parse_kib_line(line);
+        }
+    }
+    Some((total?, avail?))
+}
+
+fn parse_kib_line(line: &str) -> Option<u64> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() < 2 { return None; }
+    let kib: u64 = parts[1].parse().ok()?;
+    Some(kib * 1024)
+}
+
+fn collect_listening_ports() -> Vec<ListeningPort> {
+    let mut ports = BTreeSet::new();
+    // IPv4 TCP
+    parse_proc_net("/proc/net/tcp", "tcp", &mut ports);
+    // IPv6 TCP
+    parse_proc_net("/proc/net/tcp6", "tcp6", &mut ports);
+    // UDP v4
+    parse_proc_net("/proc/net/udp", "udp", &mut ports);
+    // UDP v6
+    parse_proc_net("/proc/net/udp6", "udp6", &mut ports);
+    ports.into_iter().collect()
+}
+
+fn parse_proc_net(path: &str, proto: &'static str, set: &mut BTreeSet<ListeningPort>) {
+    let content = match trace::read_to_string(path) { Ok(s) => s, Err(_) => return };
+    for (i, line) in content.lines().enumerate() {
+        if i == 0 { continue; }
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 10 { continue; }
+        let local = cols[1]; // ip:port in hex
+        let state = cols[3]; // 0A is LISTEN for TCP; for UDP we consider open sockets
+        if proto.starts_with("tcp") && state != "0A" { continue; }
+        if let Some((_ip_hex, port_hex)) = local.split_once(':') {
+            if let Ok(port) = u16::from_str_radix(port_hex, 16) {
+                // public if not bound to 127.0.0.1 or ::1
+                let ip_hex = &local[..local.find(':').unwrap_or(local.len())];
+                let public = !is_loopback_hex(ip_hex, proto);
+                set.insert(ListeningPort { port, proto, public });
+            }
+        }
+    }
+}
+
+fn is_loopback_hex(ip_hex: &str, proto: &str) -> bool {
+    if proto.ends_with('6') {
+        // IPv6 loopback ::1 is 00000000000000000000000000000001
+        return ip_hex == "00000000000000000000000000000001";
+    }
+    // IPv4 127.0.0.1 is 0100007F (little endian in /proc)
+    ip_hex.eq_ignore_ascii_case("0100007F")
+}
+
+impl PartialEq for ListeningPort { fn eq(&self, other: &Self) -> bool { self.port == other.port && self.proto == other.proto && self.public == other.public } }
+impl Eq for ListeningPort {}
+impl Ord for ListeningPort { fn cmp(&self, other: &Self) -> std::cmp::Ordering { (self.port, self.proto).cmp(&(other.port, other.proto)) } }
+impl PartialOrd for ListeningPort { fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> { Some(self.cmp(other)) } }
+
+pub const SSHD_CONFIG: &str = "/etc/ssh/sshd_config";
+pub const SUDOERS: &str = "/etc/sudoers";
+pub const PWQUALITY_CONF: &str = "/etc/security/pwquality.conf";
+/// Nested includes deeper than this are ignored, which also stops include loops.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+fn dump_sshd_config(privileges: &Privileges) -> Option<SshdConfigDump> {
+    let mut sources = Vec::new();
+    read_sshd_config(Path::new(SSHD_CONFIG), false, 0, &mut sources);
+
+    // Prefer robust `sshd -T` (effective config). Fallback to parsing file if sshd missing or commands are disabled.
+    if let Ok(output) = exec::run("sshd", &["-T"]) {
+        let success = output.success();
+        let message = output.error_message();
+        let invocation = Some(output.invocation);
+        if success {
             let mut map = BTreeMap::new();
-            for line in stdout.lines() {
+            for line in output.stdout.lines() {
                 if let Some((k, v)) = line.split_once(' ') {
                     map.insert(k.trim().to_string(), v.trim().to_string());
                 }
             }
-            return Some(SshdConfigDump { ok: true, values: map, stderr: None });
-        } else {
-            return Some(SshdConfigDump { ok: false, values: BTreeMap::new(), stderr: Some(String::from_utf8_lossy(&output.stderr).to_string()) });
+            return Some(SshdConfigDump { ok: true, values: map, stderr: None, sources, needs_root: false, invocation });
+        }
+        // sshd -T has to load the host keys, which regular users cannot read; sshd_config itself usually is readable
+        if privileges.can_read_all() || sources.is_empty() {
+            return Some(SshdConfigDump { ok: false, values: BTreeMap::new(), stderr: Some(message), sources, needs_root: false, invocation });
         }
+        return Some(SshdConfigDump { ok: true, values: first_values(&sources), stderr: Some(message), sources, needs_root: true, invocation });
     }
 
-    // Fallback: try to read sshd_config to avoid external deps; best-effort.
+    // Fallback: use the parsed files to avoid external deps; best-effort.
+    if trace::exists(SSHD_CONFIG) {
+        return Some(SshdConfigDump { ok: true, values: first_values(&sources), stderr: None, sources, needs_root: false, invocation: None });
+    }
+
+    None
+}
+
+/// Unconditional directives with the first value read winning, as in sshd.
+fn first_values(sources: &[ConfigLine]) -> BTreeMap<String, String> {
     let mut map = BTreeMap::new();
-    if let Ok(content) = fs::read_to_string("/etc/ssh/sshd_config") {
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') { continue; }
-            let lower = line.to_lowercase();
-            if let Some((k, v)) = lower.split_once(' ') {
-                map.insert(k.trim().to_string(), v.trim().to_string());
+    for line in sources.iter().filter(|l| !l.conditional) {
+        map.entry(line.key.clone()).or_insert_with(|| line.value.to_lowercase());
+    }
+    map
+}
+
+/// Reads `path`, expanding `Include` globs in place (relative patterns are resolved against /etc/ssh).
+fn read_sshd_config(path: &Path, conditional: bool, depth: usize, out: &mut Vec<ConfigLine>) {
+    if depth > MAX_INCLUDE_DEPTH { return; }
+    let Ok(content) = trace::read_to_string(path) else { return };
+    let mut in_match = conditional;
+    for (idx, raw) in content.lines().enumerate() {
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with('#') { continue; }
+        let (key, value) = text.split_once(|c: char| c.is_whitespace() || c == '=').unwrap_or((text, ""));
+        let key = key.to_ascii_lowercase();
+        let value = value.trim_start_matches(|c: char| c.is_whitespace() || c == '=').trim();
+        match key.as_str() {
+            // `Match all` ends the conditional block, as does the end of the file
+            "match" => in_match = !value.eq_ignore_ascii_case("all"),
+            "include" => {
+                for pattern in value.split_whitespace() {
+                    let pattern = if pattern.starts_with('/') { pattern.to_string() } else { format!("/etc/ssh/{}", pattern) };
+                    for included in glob_sorted(&pattern) { read_sshd_config(&included, in_match, depth + 1, out); }
+                }
             }
+            _ => out.push(config_line(path, idx, &key, value, text, in_match)),
         }
-        return Some(SshdConfigDump { ok: true, values: map, stderr: None });
     }
+}
 
-    None
+/// Reads sudoers, following `@include`/`#include` (relative to the including file) and `@includedir`/`#includedir`
+/// (files without a dot and not ending in `~`, like sudo itself).
+fn read_sudoers(path: &Path, depth: usize, out: &mut Vec<ConfigLine>) {
+    if depth > MAX_INCLUDE_DEPTH { return; }
+    let Ok(content) = trace::read_to_string(path) else { return };
+    let base = path.parent().unwrap_or(Path::new("/"));
+    for (idx, raw) in content.lines().enumerate() {
+        let text = raw.trim();
+        let directive = text.strip_prefix('@').or_else(|| text.strip_prefix('#').filter(|t| t.starts_with("include")));
+        if let Some((kind, target)) = directive.and_then(|d| d.split_once(char::is_whitespace)) {
+            let target = base.join(target.trim());
+            match kind {
+                "include" => read_sudoers(&target, depth + 1, out),
+                "includedir" => {
+                    let mut files: Vec<PathBuf> = fs::read_dir(&target)
+                        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+                        .unwrap_or_default();
+                    files.retain(|f| f.file_name().map(|n| n.to_string_lossy()).is_some_and(|n| !n.contains('.') && !n.ends_with('~')));
+                    files.sort();
+                    for file in files { read_sudoers(&file, depth + 1, out); }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if text.is_empty() || text.starts_with('#') { continue; }
+        let (key, value) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        out.push(config_line(path, idx, &key.to_ascii_lowercase(), value.trim(), text, false));
+    }
+}
+
+fn read_pwquality() -> Vec<ConfigLine> {
+    read_pwquality_from(Path::new(PWQUALITY_CONF))
+}
+
+/// libpwquality reads the pwquality.conf.d drop-ins before pwquality.conf; later settings override earlier ones.
+fn read_pwquality_from(conf: &Path) -> Vec<ConfigLine> {
+    let mut files = glob_sorted(&format!("{}.d/*.conf", conf.display()));
+    files.push(conf.to_path_buf());
+    let mut out = Vec::new();
+    for file in files {
+        let Ok(content) = trace::read_to_string(&file) else { continue };
+        for (idx, raw) in content.lines().enumerate() {
+            let text = raw.split('#').next().unwrap_or("").trim();
+            if text.is_empty() { continue; }
+            let (key, value) = text.split_once('=').unwrap_or((text, ""));
+            out.push(config_line(&file, idx, &key.trim().to_ascii_lowercase(), value.trim(), raw.trim(), false));
+        }
+    }
+    out
+}
+
+fn config_line(path: &Path, idx: usize, key: &str, value: &str, text: &str, conditional: bool) -> ConfigLine {
+    ConfigLine { path: path.display().to_string(), line: idx as u32 + 1, key: key.to_string(), value: value.to_string(), text: text.to_string(), conditional }
+}
+
+fn glob_sorted(pattern: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = glob::glob(pattern).map(|m| m.filter_map(Result::ok).collect()).unwrap_or_default();
+    paths.sort();
+    paths
 }
 
 
+
+/// Facts of an unprivileged run outside a container with no sshd, shared by unit tests that need `Collectors`.
+#[cfg(test)]
+pub(crate) fn sample_collectors() -> Collectors {
+    Collectors {
+        system: crate::model::sample_report().host,
+        disk: DiskInfo { total_bytes: 100 << 30, available_bytes: 40 << 30, mounts: Vec::new() },
+        memory: MemoryInfo { total_bytes: 2 << 30, available_bytes: 1 << 30 },
+        listening_ports: Vec::new(),
+        sshd: None,
+        sudoers: Vec::new(),
+        pwquality: Vec::new(),
+        files_exist: BTreeMap::new(),
+        privileges: Privileges { euid: Some(1000), capabilities: Some(Vec::new()) },
+        container: None,
+        files: None,
+        timings: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory under the system temp dir with `files` (relative path, content) written into it.
+    fn fixture(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vps-audit-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn keys(lines: &[ConfigLine]) -> Vec<(&str, &str, bool)> {
+        lines.iter().map(|l| (l.key.as_str(), l.value.as_str(), l.conditional)).collect()
+    }
+
+    #[test]
+    fn sshd_includes_expand_in_place_and_match_all_ends_the_block() {
+        let dir = fixture("sshd", &[("conf.d/10-port.conf", "Port 2222\n")]);
+        let main = dir.join("sshd_config");
+        fs::write(
+            &main,
+            format!(
+                "Include {}/conf.d/*.conf\nPermitRootLogin no\nMatch User backup\n  PasswordAuthentication yes\nMatch all\nX11Forwarding no\n",
+                dir.display()
+            ),
+        )
+        .unwrap();
+        let mut lines = Vec::new();
+        read_sshd_config(&main, false, 0, &mut lines);
+        assert_eq!(
+            keys(&lines),
+            [("port", "2222", false), ("permitrootlogin", "no", false), ("passwordauthentication", "yes", true), ("x11forwarding", "no", false)]
+        );
+        assert_eq!(lines[0].path, dir.join("conf.d/10-port.conf").display().to_string());
+        assert_eq!((lines[1].line, lines[3].line), (2, 6));
+        let values = first_values(&lines);
+        assert_eq!(values.get("x11forwarding").map(String::as_str), Some("no"));
+        assert!(!values.contains_key("passwordauthentication"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sudoers_follows_include_and_includedir() {
+        let dir = fixture(
+            "sudoers",
+            &[
+                ("sudoers", "Defaults env_reset\n@includedir sudoers.d\n#include extra\n"),
+                ("extra", "Defaults logfile=/var/log/sudo.log\n"),
+                ("sudoers.d/admins", "%admin ALL=(ALL) ALL\n"),
+                ("sudoers.d/ignored.bak", "Defaults !logfile\n"),
+                ("sudoers.d/editor~", "Defaults !logfile\n"),
+            ],
+        );
+        let mut lines = Vec::new();
+        read_sudoers(&dir.join("sudoers"), 0, &mut lines);
+        assert_eq!(
+            keys(&lines),
+            [("defaults", "env_reset", false), ("%admin", "ALL=(ALL) ALL", false), ("defaults", "logfile=/var/log/sudo.log", false)]
+        );
+        assert_eq!(lines[2].location().to_string(), format!("{}:1", dir.join("extra").display()));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn pwquality_reads_drop_ins_before_the_main_file() {
+        let dir = fixture(
+            "pwquality",
+            &[("pwquality.conf.d/50-site.conf", "minlen = 14\n"), ("pwquality.conf", "# defaults\nminlen = 8 # too short\ndictcheck=1\n")],
+        );
+        let lines = read_pwquality_from(&dir.join("pwquality.conf"));
+        assert_eq!(keys(&lines), [("minlen", "14", false), ("minlen", "8", false), ("dictcheck", "1", false)]);
+        // The last minlen wins, and it is reported where it was written
+        let last = lines.iter().rfind(|l| l.key == "minlen").unwrap();
+        assert_eq!((last.line, last.text.as_str()), (2, "minlen = 8 # too short"));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::model::{AuditReport, CheckResult, Status};
+use crate::report::escape_xml as esc;
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// Compliance frameworks checks can map to via [`crate::model::AuditCheck::controls`].
+/// Mappings are indicative: a PASS shows the tool found evidence for a control, not that the control is met in full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Framework {
+    /// CIS Ubuntu Linux 22.04 LTS Benchmark v1.0.0 numbering (Debian benchmarks use the same section layout)
+    Cis,
+    /// NIST SP 800-53 Rev. 5
+    Nist80053,
+    /// ISO/IEC 27001:2022 Annex A
+    Iso27001,
+    /// BSI IT-Grundschutz-Kompendium (Edition 2023)
+    Bsi,
+}
+
+impl Framework {
+    /// Key used in `CheckResult.controls` and the JSON compliance report.
+    pub fn key(&self) -> &'static str {
+        match self { Framework::Cis => "cis", Framework::Nist80053 => "nist-800-53", Framework::Iso27001 => "iso-27001", Framework::Bsi => "bsi" }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Framework::Cis => "CIS Ubuntu Linux 22.04 LTS Benchmark v1.0.0",
+            Framework::Nist80053 => "NIST SP 800-53 Rev. 5",
+            Framework::Iso27001 => "ISO/IEC 27001:2022 Annex A",
+            Framework::Bsi => "BSI IT-Grundschutz-Kompendium",
+        }
+    }
+
+    /// Controls in scope for a single Linux server, including those no check evaluates yet.
+    pub fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Framework::Cis => &[
+                ("1.3.1", "Ensure AIDE is installed"),
+                ("1.5.1", "Ensure address space layout randomization (ASLR) is enabled"),
+                ("1.9", "Ensure updates, patches, and additional security software are installed"),
+                ("2.4", "Ensure nonessential services are removed or masked"),
+                ("3.5.1.1", "Ensure ufw is installed"),
+                ("3.5.2.1", "Ensure nftables is installed"),
+                ("3.5.2.8", "Ensure nftables default deny firewall policy"),
+                ("4.1.1.1", "Ensure auditd is installed"),
+                ("5.2.5", "Ensure SSH LogLevel is appropriate"),
+                ("5.2.7", "Ensure SSH root login is disabled"),
+                ("5.2.9", "Ensure SSH PermitEmptyPasswords is disabled"),
+                ("5.2.12", "Ensure SSH X11 forwarding is disabled"),
+                ("5.3.3", "Ensure sudo log file exists"),
+                ("5.4.1", "Ensure password creation requirements are configured"),
+                ("6.1.13", "Audit SUID executables"),
+            ],
+            Framework::Nist80053 => &[
+                ("AC-6", "Least Privilege"),
+                ("AC-6(2)", "Least Privilege | Non-privileged Access for Nonsecurity Functions"),
+                ("AC-7", "Unsuccessful Logon Attempts"),
+                ("AC-17", "Remote Access"),
+                ("AU-2", "Event Logging"),
+                ("AU-4", "Audit Log Storage Capacity"),
+                ("AU-9", "Protection of Audit Information"),
+                ("AU-12", "Audit Record Generation"),
+                ("CM-6", "Configuration Settings"),
+                ("CM-7", "Least Functionality"),
+                ("IA-2", "Identification and Authentication (Organizational Users)"),
+                ("IA-5(1)", "Authenticator Management | Password-based Authentication"),
+                ("SC-5(2)", "Denial-of-service Protection | Capacity, Bandwidth, and Redundancy"),
+                ("SC-7", "Boundary Protection"),
+                ("SC-7(5)", "Boundary Protection | Deny by Default - Allow by Exception"),
+                ("SI-2", "Flaw Remediation"),
+                ("SI-4", "System Monitoring"),
+                ("SI-7", "Software, Firmware, and Information Integrity"),
+            ],
+            Framework::Iso27001 => &[
+                ("A.5.17", "Authentication information"),
+                ("A.8.2", "Privileged access rights"),
+                ("A.8.5", "Secure authentication"),
+                ("A.8.6", "Capacity management"),
+                ("A.8.7", "Protection against malware"),
+                ("A.8.8", "Management of technical vulnerabilities"),
+                ("A.8.9", "Configuration management"),
+                ("A.8.13", "Information backup"),
+                ("A.8.15", "Logging"),
+                ("A.8.16", "Monitoring activities"),
+                ("A.8.20", "Networks security"),
+                ("A.8.24", "Use of cryptography"),
+            ],
+            Framework::Bsi => &[
+                ("CON.3", "Backup Concept"),
+                ("NET.3.2", "Firewall"),
+                ("OPS.1.1.3", "Patch and Change Management"),
+                ("OPS.1.1.4", "Protection Against Malware"),
+                ("OPS.1.1.5", "Logging"),
+                ("ORP.4.A8", "Regulation of Password Use"),
+                ("SYS.1.1.A6", "Deactivation of Unnecessary Services"),
+                ("SYS.1.3.A4", "Protection Against Exploitation of Vulnerabilities in Applications"),
+                ("SYS.1.3.A8", "Encrypted Access via Secure Shell"),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ControlState {
+    Pass,
+    Warn,
+    Fail,
+    /// Mapped checks exist but were skipped or not part of this run
+    NotEvaluated,
+    /// No check maps to the control
+    NotCovered,
+}
+
+impl ControlState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ControlState::Pass => "PASS",
+            ControlState::Warn => "WARN",
+            ControlState::Fail => "FAIL",
+            ControlState::NotEvaluated => "NOT EVALUATED",
+            ControlState::NotCovered => "NOT COVERED",
+        }
+    }
+
+    /// FAIL over WARN over PASS among the checks that ran; SKIP counts as not evaluated.
+    fn roll_up(checks: &[MappedCheck]) -> Self {
+        let evaluated: Vec<Status> = checks.iter().filter_map(|c| c.status).filter(|s| *s != Status::Skip).collect();
+        if checks.is_empty() {
+            ControlState::NotCovered
+        } else if evaluated.iter().any(|s| s.is_fail()) {
+            ControlState::Fail
+        } else if evaluated.iter().any(|s| s.is_warn()) {
+            ControlState::Warn
+        } else if evaluated.is_empty() {
+            ControlState::NotEvaluated
+        } else {
+            ControlState::Pass
+        }
+    }
+}
+
+/// `--report-by <framework>`: results pivoted into one row per control.
+#[derive(Debug, Serialize)]
+pub struct ComplianceReport {
+    pub framework: &'static str,
+    pub name: &'static str,
+    pub host: Option<String>,
+    pub generated_at: String,
+    pub summary: ComplianceSummary,
+    pub controls: Vec<ControlStatus>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ComplianceSummary {
+    pub controls: usize,
+    pub pass: usize,
+    pub warn: usize,
+    pub fail: usize,
+    pub not_evaluated: usize,
+    pub not_covered: usize,
+    /// Share of controls with at least one evaluated check
+    pub coverage_percent: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ControlStatus {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub status: ControlState,
+    pub checks: Vec<MappedCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MappedCheck {
+    pub id: &'static str,
+    /// None when the check was not part of this run (category filter)
+    pub status: Option<Status>,
+}
+
+impl ComplianceReport {
+    /// Pivots `results` into controls; pass every result that ran, not only the ones kept by `--status`, so passing
+    /// controls are not reported as unevaluated. Host and timestamp come from `report`.
+    pub fn new(report: &AuditReport, results: &[CheckResult], framework: Framework) -> Self {
+        let registry = crate::checks::all();
+        let mut controls: Vec<ControlStatus> = framework
+            .catalog()
+            .iter()
+            .map(|&(id, title)| ControlStatus { id, title, status: ControlState::NotCovered, checks: Vec::new() })
+            .collect();
+        for check in &registry {
+            for &(_, control) in check.controls().iter().filter(|(fw, _)| *fw == framework) {
+                let idx = match controls.iter().position(|c| c.id == control) {
+                    Some(idx) => idx,
+                    None => {
+                        controls.push(ControlStatus { id: control, title: "", status: ControlState::NotCovered, checks: Vec::new() });
+                        controls.len() - 1
+                    }
+                };
+                let status = results.iter().find(|r| r.id == check.id()).map(|r| r.status);
+                controls[idx].checks.push(MappedCheck { id: check.id(), status });
+            }
+        }
+
+        let mut summary = ComplianceSummary { controls: controls.len(), ..Default::default() };
+        for control in &mut controls {
+            control.status = ControlState::roll_up(&control.checks);
+            match control.status {
+                ControlState::Pass => summary.pass += 1,
+                ControlState::Warn => summary.warn += 1,
+                ControlState::Fail => summary.fail += 1,
+                ControlState::NotEvaluated => summary.not_evaluated += 1,
+                ControlState::NotCovered => summary.not_covered += 1,
+            }
+        }
+        let evaluated = summary.pass + summary.warn + summary.fail;
+        summary.coverage_percent = if summary.controls == 0 { 0 } else { ((evaluated as f64 / summary.controls as f64) * 100.0).round() as u32 };
+
+        Self {
+            framework: framework.key(),
+            name: framework.name(),
+            host: report.host.hostname.clone(),
+            generated_at: report.generated_at.clone(),
+            summary,
+            controls,
+        }
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut s = String::new();
+        let heading = format!("{} ({})", self.name, self.host.as_deref().unwrap_or("localhost"));
+        let _ = writeln!(s, "{}\n{}\n", heading, "=".repeat(heading.chars().count()));
+        for c in &self.controls {
+            let _ = writeln!(s, "{:<12} {:<14} {}", c.id, c.status.as_str(), c.title);
+            for check in &c.checks {
+                let _ = writeln!(s, "{:<12} {:<14}   - {} ({})", "", "", check.id, check.status.map(|s| s.as_str()).unwrap_or("not run"));
+            }
+        }
+        let m = &self.summary;
+        let _ = writeln!(
+            s,
+            "\nCoverage: {}% of {} controls evaluated. PASS={} WARN={} FAIL={} NOT EVALUATED={} NOT COVERED={}",
+            m.coverage_percent, m.controls, m.pass, m.warn, m.fail, m.not_evaluated, m.not_covered
+        );
+        s
+    }
+
+    pub fn render_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut s = String::new();
+        let m = &self.summary;
+        let _ = writeln!(s, "# {}\n", self.name);
+        let _ = writeln!(s, "Host `{}`, {}\n", self.host.as_deref().unwrap_or("localhost"), self.generated_at);
+        let _ = writeln!(
+            s,
+            "**Coverage {}%** of {} controls: {} PASS, {} WARN, {} FAIL, {} not evaluated, {} not covered\n",
+            m.coverage_percent, m.controls, m.pass, m.warn, m.fail, m.not_evaluated, m.not_covered
+        );
+        let _ = writeln!(s, "| Control | Status | Title | Checks |\n|---|---|---|---|");
+        for c in &self.controls {
+            let checks: Vec<String> = c.checks.iter().map(|k| format!("`{}` {}", k.id, k.status.map(|s| s.as_str()).unwrap_or("not run"))).collect();
+            let _ = writeln!(s, "| {} | {} | {} | {} |", c.id, c.status.as_str(), c.title.replace('|', "\\|"), checks.join("<br>"));
+        }
+        s
+    }
+
+    pub fn render_html(&self) -> String {
+        let mut s = String::new();
+        let m = &self.summary;
+        let _ = writeln!(s, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(s, "<title>{}</title>\n<style>{}</style>\n</head>\n<body>", esc(self.name), crate::report::html::STYLE);
+        let _ = writeln!(s, "<h1>{}</h1>", esc(self.name));
+        let _ = writeln!(s, "<p class=\"muted\">{} &middot; {}</p>", esc(self.host.as_deref().unwrap_or("localhost")), esc(&self.generated_at));
+        let _ = writeln!(
+            s,
+            "<p><strong>Coverage {}%</strong> of {} controls: {} PASS, {} WARN, {} FAIL, {} not evaluated, {} not covered</p>",
+            m.coverage_percent, m.controls, m.pass, m.warn, m.fail, m.not_evaluated, m.not_covered
+        );
+        let _ = writeln!(s, "<table>\n<tr><th>Control</th><th>Status</th><th>Title</th><th>Checks</th></tr>");
+        for c in &self.controls {
+            let checks: Vec<String> = c
+                .checks
+                .iter()
+                .map(|k| format!("<code>{}</code> {}", esc(k.id), k.status.map(|s| s.as_str()).unwrap_or("not run")))
+                .collect();
+            let _ = writeln!(
+                s,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                esc(c.id), c.status.as_str(), esc(c.title), checks.join("<br>")
+            );
+        }
+        let _ = writeln!(s, "</table>\n</body>\n</html>");
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_report;
+
+    fn mapped(statuses: &[Option<Status>]) -> Vec<MappedCheck> {
+        statuses.iter().map(|&status| MappedCheck { id: "test", status }).collect()
+    }
+
+    #[test]
+    fn fail_beats_warn_beats_pass_and_skip_is_ignored() {
+        use Status::*;
+        assert_eq!(ControlState::roll_up(&mapped(&[Some(Pass), Some(Fail), Some(Warn)])), ControlState::Fail);
+        assert_eq!(ControlState::roll_up(&mapped(&[Some(Pass), Some(Skip), Some(Warn)])), ControlState::Warn);
+        assert_eq!(ControlState::roll_up(&mapped(&[Some(Pass), Some(Skip)])), ControlState::Pass);
+        assert_eq!(ControlState::roll_up(&mapped(&[Some(Skip), None])), ControlState::NotEvaluated);
+        assert_eq!(ControlState::roll_up(&[]), ControlState::NotCovered);
+    }
+
+    #[test]
+    fn status_filter_does_not_hide_passing_controls() {
+        let report = sample_report();
+        let control = |c: &ComplianceReport, id: &str| c.controls.iter().find(|c| c.id == id).unwrap().status;
+        let full = ComplianceReport::new(&report, &report.results, Framework::Nist80053);
+        // IA-2 is only mapped to ssh.password_auth, which passes; AC-17 also maps to the failing ssh.root_login
+        assert_eq!(control(&full, "IA-2"), ControlState::Pass);
+        assert_eq!(control(&full, "AC-17"), ControlState::Fail);
+
+        let failing: Vec<CheckResult> = report.results.iter().filter(|r| r.status.is_fail()).cloned().collect();
+        let filtered = ComplianceReport::new(&report, &failing, Framework::Nist80053);
+        assert_eq!(control(&filtered, "IA-2"), ControlState::NotEvaluated);
+        assert!(filtered.summary.coverage_percent < full.summary.coverage_percent);
+    }
+
+    #[test]
+    fn coverage_counts_controls_with_an_evaluated_check() {
+        let report = sample_report();
+        let c = ComplianceReport::new(&report, &report.results, Framework::Nist80053);
+        let m = &c.summary;
+        assert_eq!(m.controls, c.controls.len());
+        assert_eq!(m.pass + m.warn + m.fail + m.not_evaluated + m.not_covered, m.controls);
+        let evaluated = (m.pass + m.warn + m.fail) as f64;
+        assert_eq!(m.coverage_percent, (evaluated * 100.0 / m.controls as f64).round() as u32);
+        assert_eq!(ComplianceReport::new(&report, &[], Framework::Nist80053).summary.coverage_percent, 0);
+    }
+}
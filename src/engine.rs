@@ -1,8 +1,10 @@
 use crate::collectors::Collectors;
-use crate::model::{AuditCheck, CheckResult, Status};
+use crate::model::{AuditCheck, AuditReport, CheckResult, Prerequisite, Status};
+use crate::trace;
+use std::collections::BTreeMap;
 use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 pub struct AuditEngine {
     categories_filter: Option<Vec<String>>,
@@ -14,69 +16,202 @@ impl AuditEngine {
         Self { categories_filter, checks: Vec::new() }
     }
 
-    pub fn register<C: AuditCheck + 'static>(&mut self, check: C) {
-        self.checks.push(Box::new(check));
+    pub fn register_default_checks(&mut self) {
+        self.checks.extend(crate::checks::all());
     }
 
-    pub fn register_default_checks(&mut self) {
-        use crate::checks::*;
-        self.register(ssh::SshRootLoginCheck);
-        self.register(ssh::SshPasswordAuthCheck);
-        self.register(ssh::SshPortCheck);
-        self.register(system::RebootRequiredCheck);
-        self.register(system::DiskUsageCheck);
-        self.register(system::MemoryUsageCheck);
-        self.register(system::CpuUsageCheck);
-        self.register(policy::SudoLoggingCheck);
-        self.register(policy::PasswordPolicyCheck);
-        self.register(files::SuidFilesCheck);
-        self.register(network::ListeningPortsCheck);
-        self.register(firewall::FirewallPresenceCheck);
-        self.register(firewall::NftablesRulesCheck);
-    }
-
-    pub fn run_all(&self) -> Vec<CheckResult> {
-        let collectors = Collectors::collect();
-        let mut results = Vec::with_capacity(self.checks.len());
+    /// Collects facts, runs every registered check and wraps the results in a report envelope.
+    pub fn audit(&self) -> AuditReport {
+        self.audit_with(|_| {})
+    }
+
+    /// Like [`AuditEngine::audit`], but hands every result to `on_result` as soon as its check completes.
+    pub fn audit_with(&self, on_result: impl FnMut(&CheckResult)) -> AuditReport {
+        self.audit_with_facts(on_result).0
+    }
+
+    /// Like [`AuditEngine::audit_with`], also returning the facts the checks ran on.
+    pub fn audit_with_facts(&self, on_result: impl FnMut(&CheckResult)) -> (AuditReport, Collectors) {
+        let started = SystemTime::now();
+        let timer = Instant::now();
+        let facts = Collectors::collect(self.uses_file_index());
+        let results = self.run_with(&facts, on_result);
+        (AuditReport::new(&facts, results, started, timer.elapsed(), self.categories_filter.clone()), facts)
+    }
+
+    /// Runs the selected checks, and the checks they depend on, in dependency order, calling `on_result` for each
+    /// result before moving on to the next check. Checks with an unmet prerequisite are skipped without running.
+    /// Results come back in registration order; dependencies outside the category filter are not reported.
+    pub fn run_with(&self, collectors: &Collectors, mut on_result: impl FnMut(&CheckResult)) -> Vec<CheckResult> {
+        let selected: Vec<bool> = self.checks.iter().map(|c| self.selected(c.as_ref())).collect();
+        let mut done: BTreeMap<&str, CheckResult> = BTreeMap::new();
         // Per-check timeout budget to avoid long hangs (e.g., massive filesystem walks)
         let timeout = Duration::from_secs(5);
 
         thread::scope(|scope| {
-            'outer: for check in &self.checks {
-                if let Some(filter) = &self.categories_filter {
-                    let categories: Vec<String> = check.categories().iter().map(|s| s.to_string()).collect();
-                    let matches_any = filter.iter().any(|wanted| {
-                        categories.iter().any(|c| c.eq_ignore_ascii_case(wanted))
+            for idx in self.execution_order(&selected) {
+                let check = &self.checks[idx];
+                let unmet = check.prerequisites().iter().find_map(|p| p.unmet(collectors, &done).map(|detail| (p, detail)));
+                let result = if let Some((prerequisite, detail)) = unmet {
+                    CheckResult::prerequisite_not_met(check.as_ref(), prerequisite, collectors, &detail)
+                } else {
+                    let (tx, rx) = mpsc::channel();
+                    let collectors_clone = collectors.clone();
+                    let start = Instant::now();
+                    scope.spawn(move || {
+                        let result = check.run(&collectors_clone);
+                        let _ = tx.send(result);
                     });
-                    if !matches_any { continue 'outer; }
-                }
-
-                let (tx, rx) = mpsc::channel();
-                let collectors_clone = collectors.clone();
-                scope.spawn(move || {
-                    let result = check.run(&collectors_clone);
-                    let _ = tx.send(result);
-                });
-
-                match rx.recv_timeout(timeout) {
-                    Ok(result) => results.push(result),
-                    Err(_) => {
-                        results.push(CheckResult {
-                            id: check.id().to_string(),
-                            title: check.title().to_string(),
-                            categories: check.categories().iter().map(|s| s.to_string()).collect(),
-                            status: Status::Skip,
-                            reason: format!("Check timed out after {}s", timeout.as_secs()),
-                            remediation: Some("Re-run with narrower categories or open an issue if this persists".into()),
-                            evidence: None,
-                        });
+
+                    match rx.recv_timeout(timeout) {
+                        Ok(result) => CheckResult { duration_ms: start.elapsed().as_millis() as u64, ..result },
+                        Err(_) => {
+                            trace::event("timeout", || format!("check {} after {}s", check.id(), timeout.as_secs()));
+                            CheckResult {
+                                status: Status::Skip,
+                                reason: format!("Check timed out after {}s", timeout.as_secs()),
+                                remediation: Some("Re-run with narrower categories or open an issue if this persists".into()),
+                                evidence: None,
+                                duration_ms: start.elapsed().as_millis() as u64,
+                                ..CheckResult::for_check(check.as_ref())
+                            }
+                        }
                     }
-                }
+                };
+                trace::event("check", || format!("{} {} in {}ms: {}", check.id(), result.status.as_str(), result.duration_ms, result.reason));
+                if selected[idx] { on_result(&result); }
+                done.insert(check.id(), result);
             }
         });
 
-        results
+        self.checks.iter().zip(&selected).filter(|(_, s)| **s).filter_map(|(c, _)| done.remove(c.id())).collect()
+    }
+
+    fn uses_file_index(&self) -> bool {
+        let selected: Vec<bool> = self.checks.iter().map(|c| self.selected(c.as_ref())).collect();
+        self.execution_order(&selected).into_iter().any(|i| self.checks[i].uses_file_index())
+    }
+
+    fn selected(&self, check: &dyn AuditCheck) -> bool {
+        let Some(filter) = &self.categories_filter else { return true };
+        filter.iter().any(|wanted| check.categories().iter().any(|c| c.eq_ignore_ascii_case(wanted)))
+    }
+
+    /// Indices of the selected checks plus the checks they transitively depend on, each after its dependencies and
+    /// otherwise in registration order. Checks in a dependency cycle go last and report their prerequisite unmet.
+    fn execution_order(&self, selected: &[bool]) -> Vec<usize> {
+        let index: BTreeMap<&str, usize> = self.checks.iter().enumerate().map(|(i, c)| (c.id(), i)).collect();
+        let deps = |i: usize| -> Vec<usize> {
+            self.checks[i]
+                .prerequisites()
+                .iter()
+                .filter_map(|p| match p { Prerequisite::Check(id) => index.get(id).copied(), _ => None })
+                .collect()
+        };
+
+        let mut needed = selected.to_vec();
+        let mut stack: Vec<usize> = (0..needed.len()).filter(|&i| needed[i]).collect();
+        while let Some(i) = stack.pop() {
+            for dep in deps(i) {
+                if !needed[dep] { needed[dep] = true; stack.push(dep); }
+            }
+        }
+
+        let mut placed = vec![false; needed.len()];
+        let mut order = Vec::new();
+        while let Some(next) = (0..needed.len()).find(|&i| needed[i] && !placed[i] && deps(i).iter().all(|&d| placed[d])) {
+            placed[next] = true;
+            order.push(next);
+        }
+        order.extend((0..needed.len()).filter(|&i| needed[i] && !placed[i]));
+        order
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::sample_collectors;
 
+    struct Fake {
+        id: &'static str,
+        categories: &'static [&'static str],
+        needs: &'static [Prerequisite],
+        status: Status,
+    }
+
+    impl AuditCheck for Fake {
+        fn id(&self) -> &'static str { self.id }
+        fn title(&self) -> &'static str { self.id }
+        fn categories(&self) -> &'static [&'static str] { self.categories }
+        fn prerequisites(&self) -> &'static [Prerequisite] { self.needs }
+        fn run(&self, _ctx: &Collectors) -> CheckResult {
+            CheckResult { status: self.status, reason: format!("{} ran", self.id), ..CheckResult::for_check(self) }
+        }
+    }
+
+    fn engine(filter: Option<&str>, checks: Vec<Fake>) -> AuditEngine {
+        let mut engine = AuditEngine::new(filter.map(|f| vec![f.to_string()]));
+        engine.checks = checks.into_iter().map(|c| Box::new(c) as Box<dyn AuditCheck>).collect();
+        engine
+    }
+
+    fn fake(id: &'static str, categories: &'static [&'static str], needs: &'static [Prerequisite], status: Status) -> Fake {
+        Fake { id, categories, needs, status }
+    }
+
+    #[test]
+    fn dependencies_run_first_and_stay_out_of_filtered_results() {
+        let engine = engine(Some("web"), vec![
+            fake("web.tls", &["web"], &[Prerequisite::Check("web.installed")], Status::Pass),
+            fake("other.check", &["other"], &[], Status::Pass),
+            fake("web.installed", &["base"], &[], Status::Pass),
+        ]);
+        let selected = [true, false, false];
+        assert_eq!(engine.execution_order(&selected), [2, 0]);
+
+        let mut reported = Vec::new();
+        let results = engine.run_with(&sample_collectors(), |r| reported.push(r.id.clone()));
+        assert_eq!(reported, ["web.tls"]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status, Status::Pass);
+    }
+
+    #[test]
+    fn failed_prerequisite_skips_without_running() {
+        let engine = engine(None, vec![
+            fake("a", &["t"], &[], Status::Fail),
+            fake("b", &["t"], &[Prerequisite::Check("a")], Status::Pass),
+            fake("c", &["t"], &[Prerequisite::Check("b")], Status::Pass),
+        ]);
+        let results = engine.run_with(&sample_collectors(), |_| {});
+        assert_eq!(results[1].status, Status::Skip);
+        assert_eq!(results[1].reason, "Prerequisite 'a' not met (FAIL: a ran)");
+        // The skip propagates: a skipped check does not satisfy the checks depending on it
+        assert_eq!(results[2].status, Status::Skip);
+        assert!(results[2].reason.starts_with("Prerequisite 'b' not met (SKIP: "), "{}", results[2].reason);
+    }
+
+    #[test]
+    fn sshd_checks_are_skipped_before_they_read_the_config() {
+        use crate::checks::ssh::{SshPasswordAuthCheck, SshPortCheck, SshRootLoginCheck};
+        let mut engine = AuditEngine::new(None);
+        engine.checks = vec![Box::new(SshRootLoginCheck), Box::new(SshPasswordAuthCheck), Box::new(SshPortCheck)];
+        let results = engine.run_with(&sample_collectors(), |_| {});
+        assert!(results.iter().all(|r| r.status == Status::Skip && r.reason.ends_with("(sshd_config not found)")), "{:?}", results);
+    }
+
+    #[test]
+    fn cycles_go_last_and_are_skipped() {
+        let engine = engine(None, vec![
+            fake("x", &["t"], &[Prerequisite::Check("y")], Status::Pass),
+            fake("y", &["t"], &[Prerequisite::Check("x")], Status::Pass),
+            fake("z", &["t"], &[], Status::Pass),
+        ]);
+        assert_eq!(engine.execution_order(&[true; 3]), [2, 0, 1]);
+
+        let results = engine.run_with(&sample_collectors(), |_| {});
+        assert!(results[..2].iter().all(|r| r.status == Status::Skip && r.reason.starts_with("Prerequisite")));
+        assert_eq!(results[2].status, Status::Pass);
+    }
+}
@@ -0,0 +1,264 @@
+//! Runs external programs for collectors and checks: binaries only from trusted system directories, an empty
+//! environment with the C locale, a timeout and capped output. `--no-exec` turns it off entirely.
+use crate::trace;
+use anyhow::{bail, Context};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Directories programs are resolved from, in order; `$PATH` is never consulted.
+pub const TRUSTED_DIRS: &[&str] = &["/usr/sbin", "/usr/bin", "/sbin", "/bin"];
+/// Every program collectors run; `--sandbox` only lets these execute.
+pub const HELPERS: &[&str] = &["sshd"];
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Per stream; the rest is discarded and the invocation marked truncated
+const MAX_OUTPUT: u64 = 1024 * 1024;
+/// How long output capture may take after the process ended, in case something still holds the pipes open
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// `--no-exec`: every later [`run`] fails without starting a process.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn disabled() -> bool {
+    DISABLED.load(Ordering::Relaxed)
+}
+
+/// What was run and how it ended, recorded in the evidence of results that depend on it.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Invocation {
+    /// Resolved absolute path of the program
+    pub program: String,
+    pub args: Vec<String>,
+    /// `null` when the process was killed (timeout or signal)
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    /// stdout or stderr exceeded the output cap and was cut
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    pub invocation: Invocation,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.invocation.exit_code == Some(0)
+    }
+
+    /// stderr, or how the process ended when it printed nothing there.
+    pub fn error_message(&self) -> String {
+        let stderr = self.stderr.trim();
+        if !stderr.is_empty() { return stderr.to_string(); }
+        let ended = match self.invocation.exit_code {
+            Some(code) => format!("exited with code {}", code),
+            None => "was killed by a signal".to_string(),
+        };
+        format!("{} {}{}", self.invocation.program, ended, if self.invocation.truncated { " after exceeding the output limit" } else { "" })
+    }
+}
+
+/// Runs `program` (a bare name such as `sshd`) with `args`. Fails when external commands are disabled, the program
+/// is not in [`TRUSTED_DIRS`], it cannot be started or it times out; a non-zero exit is not an error.
+pub fn run(program: &str, args: &[&str]) -> anyhow::Result<CommandOutput> {
+    if disabled() {
+        trace::event("exec", || format!("{} skipped, external commands are disabled", program));
+        bail!("external commands are disabled (--no-exec)");
+    }
+    let Some(path) = resolve(program) else {
+        trace::event("exec", || format!("{} not found in {}", program, TRUSTED_DIRS.join(":")));
+        bail!("{} not found in {}", program, TRUSTED_DIRS.join(":"));
+    };
+    execute(&path, args, &[], None, TIMEOUT)
+}
+
+/// Runs a program the operator configured, such as a notification hook: an absolute path, or a bare name from
+/// [`TRUSTED_DIRS`]. Same environment, process group and output limits as [`run`], plus `env`, `stdin` and its own
+/// `timeout`.
+pub fn run_configured(program: &str, args: &[&str], env: &[(&str, &str)], stdin: &[u8], timeout: Duration) -> anyhow::Result<CommandOutput> {
+    if disabled() { bail!("external commands are disabled (--no-exec)"); }
+    let path = if program.starts_with('/') { PathBuf::from(program) } else {
+        resolve(program).with_context(|| format!("{} not found in {}; use an absolute path", program, TRUSTED_DIRS.join(":")))?
+    };
+    execute(&path, args, env, Some(stdin), timeout)
+}
+
+fn execute(path: &Path, args: &[&str], env: &[(&str, &str)], stdin: Option<&[u8]>, timeout: Duration) -> anyhow::Result<CommandOutput> {
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .env_clear()
+        .env("PATH", TRUSTED_DIRS.join(":"))
+        .env("LC_ALL", "C")
+        .envs(env.iter().copied())
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // Own process group, so a timeout also kills children that would keep the pipes open
+        .process_group(0);
+    let line = trace::command_line(&command);
+    let start = Instant::now();
+    let mut child = command.spawn().with_context(|| format!("failed to run {}", line))?;
+    let stdout = capture(child.stdout.take());
+    let stderr = capture(child.stderr.take());
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), stdin) {
+        // On its own thread: a program that does not read stdin must not block the run. A closed pipe is not an error.
+        let input = input.to_vec();
+        thread::spawn(move || { let _ = pipe.write_all(&input); });
+    }
+
+    let group = child.id() as i32;
+    let mut timed_out = false;
+    while !exited(group)? {
+        if start.elapsed() > timeout { timed_out = true; break; }
+        thread::sleep(Duration::from_millis(10));
+    }
+    // The helper is not reaped yet, so its pid, and with it the group id, cannot be handed out again. Killing the
+    // group also ends background children that would keep the pipes open; an empty group fails with ESRCH.
+    // SAFETY: kill(2) with a negative pid only signals processes in the group created above
+    unsafe { libc::kill(-group, libc::SIGKILL) };
+    let status = child.wait()?;
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    let (stdout, stdout_cut) = drain(&stdout, deadline);
+    let (stderr, stderr_cut) = drain(&stderr, deadline);
+    let invocation = Invocation {
+        program: path.display().to_string(),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        exit_code: status.code(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        timed_out,
+        truncated: stdout_cut || stderr_cut,
+    };
+    trace::event(if timed_out { "timeout" } else { "exec" }, || {
+        format!(
+            "{} -> {} in {}ms{}, stdout {:?}, stderr {:?}",
+            line,
+            status,
+            invocation.duration_ms,
+            if invocation.truncated { " (output truncated)" } else { "" },
+            trace::truncate(&stdout),
+            trace::truncate(&stderr)
+        )
+    });
+    trace::record(&line, "stdout", Ok(stdout.as_bytes()));
+    if !stderr.is_empty() { trace::record(&line, "stderr", Ok(stderr.as_bytes())); }
+    if timed_out { bail!("{} timed out after {}s", line, timeout.as_secs()); }
+    Ok(CommandOutput { invocation, stdout, stderr })
+}
+
+/// Whether the child `pid` has exited, without reaping it.
+fn exited(pid: i32) -> std::io::Result<bool> {
+    // SAFETY: an all-zero siginfo_t is valid; waitid only writes to it
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    // SAFETY: WNOWAIT leaves the child waitable, so `Child::wait` still reaps it
+    let rc = unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, libc::WEXITED | libc::WNOWAIT | libc::WNOHANG) };
+    if rc != 0 { return Err(std::io::Error::last_os_error()); }
+    // With WNOHANG, si_pid stays 0 while the child is still running
+    // SAFETY: si_pid is set for every WEXITED result and zero otherwise
+    Ok(unsafe { info.si_pid() } != 0)
+}
+
+/// Absolute path of a bare program name in [`TRUSTED_DIRS`].
+pub fn resolve(program: &str) -> Option<PathBuf> {
+    if program.contains('/') { return None; }
+    TRUSTED_DIRS
+        .iter()
+        .map(|dir| Path::new(dir).join(program))
+        .find(|p| p.metadata().is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0))
+}
+
+/// Reads a pipe up to [`MAX_OUTPUT`] on its own thread so a chatty child cannot block on a full pipe.
+fn capture(pipe: Option<impl Read + Send + 'static>) -> Receiver<(String, bool)> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let Some(pipe) = pipe else { return tx.send((String::new(), false)) };
+        let mut buf = Vec::new();
+        let mut limited = pipe.take(MAX_OUTPUT + 1);
+        let _ = limited.read_to_end(&mut buf);
+        let truncated = buf.len() as u64 > MAX_OUTPUT;
+        buf.truncate(MAX_OUTPUT as usize);
+        // Dropping the pipe here makes further writes fail with EPIPE instead of blocking
+        tx.send((String::from_utf8_lossy(&buf).into_owned(), truncated))
+    });
+    rx
+}
+
+/// Output captured by [`capture`], or nothing marked truncated when the pipe is still open at `deadline`.
+/// The reader thread is then left behind; it ends once the last writer exits.
+fn drain(output: &Receiver<(String, bool)>, deadline: Instant) -> (String, bool) {
+    output.recv_timeout(deadline.saturating_duration_since(Instant::now())).unwrap_or((String::new(), true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_output_and_exit_code() {
+        let output = run("sh", &["-c", "echo out; echo err >&2; exit 3"]).unwrap();
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+        assert_eq!(output.invocation.exit_code, Some(3));
+        assert!(!output.success() && !output.invocation.truncated);
+    }
+
+    #[test]
+    fn background_children_do_not_hold_the_run_open() {
+        let start = Instant::now();
+        let output = run("sh", &["-c", "sleep 30 & echo started"]).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5), "took {:?}", start.elapsed());
+        assert!(output.success());
+        assert_eq!(output.stdout, "started\n");
+    }
+
+    #[test]
+    fn grandchildren_are_killed_with_the_helper() {
+        let output = run("sh", &["-c", "sleep 30 & echo $!"]).unwrap();
+        let pid: i32 = output.stdout.trim().parse().unwrap();
+        // Reparented to init once the shell exits, so it may linger as a zombie until reaped
+        let gone = || fs_state(pid).is_none_or(|state| state == 'Z');
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !gone() && Instant::now() < deadline { thread::sleep(Duration::from_millis(10)); }
+        assert!(gone(), "background sleep {} still running", pid);
+    }
+
+    /// Process state letter from /proc/<pid>/stat, `None` when the process is gone.
+    fn fs_state(pid: i32) -> Option<char> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        stat.rsplit_once(')')?.1.trim_start().chars().next()
+    }
+
+    #[test]
+    fn configured_programs_get_stdin_env_and_their_own_timeout() {
+        let output = run_configured("sh", &["-c", "echo \"$GREETING $(cat)\""], &[("GREETING", "hello")], b"world", TIMEOUT).unwrap();
+        assert_eq!(output.stdout, "hello world\n");
+
+        let start = Instant::now();
+        let error = run_configured("sh", &["-c", "sleep 30"], &[], b"", Duration::from_millis(200)).unwrap_err();
+        assert!(error.to_string().contains("timed out"), "{}", error);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn only_bare_names_from_trusted_dirs_resolve() {
+        assert!(resolve("sh").is_some_and(|p| TRUSTED_DIRS.iter().any(|d| p.starts_with(d))));
+        assert!(resolve("/bin/sh").is_none());
+        assert!(resolve("../bin/sh").is_none());
+        assert!(resolve("no-such-program-vps-audit").is_none());
+    }
+}
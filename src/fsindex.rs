@@ -0,0 +1,524 @@
+//! One parallel walk over every local filesystem, shared by all file checks. Each mount from
+//! `/proc/self/mountinfo` is walked on its own without crossing into other mounts; pseudo, network and image
+//! filesystems (and everything mounted below them) are skipped.
+use crate::output;
+use crate::trace;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Stops the walk on very large or very slow filesystems; the index is then marked incomplete.
+const BUDGET: Duration = Duration::from_secs(60);
+const CACHE_FILE: &str = "scan-cache.json";
+const CACHE_VERSION: u32 = 1;
+/// Cached listings older than this are read again, in case a filesystem does not keep directory timestamps reliably.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+static FULL_SCAN: AtomicBool = AtomicBool::new(false);
+/// `serve --rescan`: how long a walk is reused; zero walks on every collection.
+static REUSE_FOR: Mutex<Duration> = Mutex::new(Duration::ZERO);
+static LAST_WALK: Mutex<Option<(Instant, Arc<FileIndex>)>> = Mutex::new(None);
+
+/// Filesystems without files worth auditing.
+const PSEUDO: &[&str] = &[
+    "proc", "sysfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "debugfs", "tracefs", "securityfs", "pstore", "bpf", "configfs",
+    "fusectl", "mqueue", "hugetlbfs", "autofs", "binfmt_misc", "efivarfs", "nsfs", "rpc_pipefs", "selinuxfs", "ramfs",
+];
+/// Remote filesystems: slow to walk and audited on their own server.
+const NETWORK: &[&str] = &["nfs", "nfs4", "cifs", "smb3", "smbfs", "ceph", "glusterfs", "afs", "9p", "fuse.sshfs", "fuse.rclone"];
+/// Read-only package images (snaps) and ISO mounts.
+const IMAGES: &[&str] = &["squashfs", "iso9660", "udf"];
+/// Not walked, with everything mounted below: container image layers (files there belong to containers, not the
+/// host), runtime state, temporary files and removable or manually mounted media.
+const SKIPPED_PATHS: &[&str] = &["/var/lib/docker", "/var/lib/containers", "/run", "/tmp", "/mnt", "/media", "/snap", "/boot/efi"];
+
+/// Metadata of one filesystem entry (not following symlinks).
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    /// Raw `st_mode`, file type bits included
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl FileEntry {
+    pub fn is_file(&self) -> bool {
+        self.mode & libc::S_IFMT == libc::S_IFREG
+    }
+
+    /// Permission bits including setuid, setgid and sticky.
+    pub fn permissions(&self) -> u32 {
+        self.mode & 0o7777
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mount {
+    pub path: PathBuf,
+    pub fstype: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileIndex {
+    /// Sorted by path bytes
+    pub entries: Vec<FileEntry>,
+    pub walked: Vec<Mount>,
+    /// Mounts left out because of their filesystem type or location, or because they repeat a walked one
+    pub skipped: Vec<Mount>,
+    /// Directories that could not be listed (permission denied)
+    pub unreadable: Vec<PathBuf>,
+    /// Entries found through a cached directory listing instead of reading the directory again; their metadata
+    /// is always read fresh
+    pub cached_entries: usize,
+    /// The walk hit [`BUDGET`] before finishing
+    pub timed_out: bool,
+    pub duration_ms: u64,
+}
+
+impl FileIndex {
+    /// Walks every local filesystem with one thread per CPU, reusing the cached listing of unchanged directories
+    /// unless `--full-scan` was given, and saves the updated cache.
+    pub fn collect() -> Self {
+        let start = Instant::now();
+        let (walked, skipped) = mounts();
+        let cache_path = cache_path();
+        let cache = cache_path.as_deref().filter(|_| !FULL_SCAN.load(Ordering::Relaxed)).and_then(ScanCache::load);
+        let queue = Queue { state: Mutex::new(QueueState::default()), ready: Condvar::new() };
+        {
+            let mut state = queue.state.lock().unwrap();
+            for mount in &walked {
+                let Ok(meta) = fs::symlink_metadata(&mount.path) else { continue };
+                state.entries.push(entry(mount.path.clone(), &meta));
+                if meta.is_dir() { state.dirs.push((mount.path.clone(), Stamp::of(&meta))); }
+            }
+        }
+        let excluded: BTreeSet<PathBuf> = skipped.iter().map(|m| m.path.clone()).chain(SKIPPED_PATHS.iter().map(PathBuf::from)).collect();
+        let walk = Walk { excluded, cache: cache.as_ref(), now: unix_now(), start };
+        thread::scope(|scope| {
+            for _ in 0..num_cpus::get().max(2) { scope.spawn(|| queue.work(&walk)); }
+        });
+
+        let mut state = queue.state.into_inner().unwrap();
+        if state.timed_out {
+            // Keep what the last run knew about the directories this one did not reach
+            if let Some(old) = cache { for (dir, cached) in old.dirs { state.cache.entry(dir).or_insert(cached); } }
+        }
+        if let Some(path) = &cache_path { ScanCache { version: CACHE_VERSION, dirs: state.cache }.save(path); }
+        let mut entries = state.entries;
+        // Byte order: much cheaper than comparing paths component by component
+        entries.sort_unstable_by(|a, b| a.path.as_os_str().cmp(b.path.as_os_str()));
+        // A bind-mounted file is both a mount root and an entry of its parent directory
+        entries.dedup_by(|a, b| a.path == b.path);
+        let mut unreadable = state.unreadable;
+        unreadable.sort();
+        let index = FileIndex {
+            cached_entries: state.cached_entries.min(entries.len()),
+            entries,
+            walked,
+            skipped,
+            unreadable,
+            timed_out: state.timed_out,
+            duration_ms: start.elapsed().as_millis() as u64,
+        };
+        trace::event("walk", || {
+            format!(
+                "{} entries ({} from the scan cache) on {} filesystems in {}ms, {} unreadable directories{}",
+                index.entries.len(),
+                index.cached_entries,
+                index.walked.len(),
+                index.duration_ms,
+                index.unreadable.len(),
+                if index.timed_out { ", stopped at the time budget" } else { "" }
+            )
+        });
+        index
+    }
+
+    /// [`FileIndex::collect`], or the previous walk while it is younger than the [`reuse_for`] window.
+    pub fn shared() -> Arc<Self> {
+        let window = *REUSE_FOR.lock().unwrap();
+        if window.is_zero() { return Arc::new(Self::collect()); }
+        let mut last = LAST_WALK.lock().unwrap();
+        if let Some((walked_at, index)) = last.as_ref().filter(|(walked_at, _)| walked_at.elapsed() < window) {
+            trace::event("walk", || format!("reusing the walk from {}s ago", walked_at.elapsed().as_secs()));
+            return Arc::clone(index);
+        }
+        let index = Arc::new(Self::collect());
+        *last = Some((Instant::now(), Arc::clone(&index)));
+        index
+    }
+
+    /// Regular files
+    pub fn files(&self) -> impl Iterator<Item = &FileEntry> {
+        self.entries.iter().filter(|e| e.is_file())
+    }
+
+    pub fn complete(&self) -> bool {
+        !self.timed_out && self.unreadable.is_empty()
+    }
+
+    /// Share of entries found by listing their directory in this run rather than from the scan cache, in percent.
+    pub fn fresh_percent(&self) -> u64 {
+        if self.entries.is_empty() { return 100; }
+        ((self.entries.len() - self.cached_entries) * 100 / self.entries.len()) as u64
+    }
+}
+
+/// `--full-scan`: ignore the scan cache for the rest of the process (it is still rewritten).
+pub fn ignore_cache() {
+    FULL_SCAN.store(true, Ordering::Relaxed);
+}
+
+/// Directory the scan cache lives in: `/var/cache/vps-audit` for root, `$XDG_CACHE_HOME/vps-audit` or
+/// `~/.cache/vps-audit` otherwise.
+pub fn cache_dir() -> Option<PathBuf> {
+    // SAFETY: geteuid cannot fail
+    if unsafe { libc::geteuid() } == 0 { return Some(PathBuf::from("/var/cache/vps-audit")); }
+    let base = std::env::var_os("XDG_CACHE_HOME").map(PathBuf::from).or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".cache")))?;
+    Some(base.join("vps-audit"))
+}
+
+/// Creates [`cache_dir`] ahead of time, for `--sandbox` to allow writing to it.
+pub fn create_cache_dir() -> Option<PathBuf> {
+    let dir = cache_dir()?;
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir).ok()?;
+    Some(dir)
+}
+
+fn cache_path() -> Option<PathBuf> {
+    cache_dir().map(|d| d.join(CACHE_FILE))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// What identifies an unchanged directory: adding, removing or renaming an entry updates mtime and ctime, and ctime
+/// cannot be set back by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    dev: u64,
+    ino: u64,
+    mtime_ns: i64,
+    ctime_ns: i64,
+}
+
+impl Stamp {
+    fn of(meta: &fs::Metadata) -> Self {
+        Stamp {
+            dev: meta.dev(),
+            ino: meta.ino(),
+            mtime_ns: meta.mtime() * 1_000_000_000 + meta.mtime_nsec(),
+            ctime_ns: meta.ctime() * 1_000_000_000 + meta.ctime_nsec(),
+        }
+    }
+}
+
+/// Names in one directory as of `scanned_at` (Unix seconds). Only the listing is cached: a `chmod u+s` on an entry
+/// does not touch its directory, so the metadata of every entry is read again on each run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedDir {
+    stamp: Stamp,
+    scanned_at: u64,
+    names: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScanCache {
+    version: u32,
+    dirs: HashMap<PathBuf, CachedDir>,
+}
+
+impl ScanCache {
+    fn load(path: &Path) -> Option<Self> {
+        // Read directly: the cache is our own state, not a host file for `--bundle`
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str::<ScanCache>(&content) {
+            Ok(cache) if cache.version == CACHE_VERSION => Some(cache),
+            Ok(_) => None,
+            Err(e) => {
+                trace::event("cache", || format!("{} ignored: {}", path.display(), e));
+                None
+            }
+        }
+    }
+
+    /// Best effort: a run without write access to the cache directory simply scans in full next time.
+    fn save(&self, path: &Path) {
+        let result = path.parent().map_or(Ok(()), |dir| fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)).and_then(|_| {
+            let json = serde_json::to_vec(self).map_err(io::Error::other)?;
+            output::write_atomic(path, &json)
+        });
+        trace::event("cache", || match &result {
+            Ok(()) => format!("saved {} directories to {}", self.dirs.len(), path.display()),
+            Err(e) => format!("saving {} failed: {}", path.display(), e),
+        });
+    }
+}
+
+struct Walk<'a> {
+    excluded: BTreeSet<PathBuf>,
+    cache: Option<&'a ScanCache>,
+    now: u64,
+    start: Instant,
+}
+
+/// Result of listing one directory.
+#[derive(Default)]
+struct Listing {
+    entries: Vec<FileEntry>,
+    subdirs: Vec<(PathBuf, Stamp)>,
+    denied: bool,
+    /// Taken from the cache
+    cached: bool,
+    /// What to store in the new cache; `None` for unreadable directories and names that are not UTF-8
+    store: Option<CachedDir>,
+}
+
+/// `serve --rescan`: later collections reuse a walk for up to `window` instead of walking for every refresh.
+pub fn reuse_for(window: Duration) {
+    *REUSE_FOR.lock().unwrap() = window;
+}
+
+#[derive(Default)]
+struct QueueState {
+    /// Directories still to list
+    dirs: Vec<(PathBuf, Stamp)>,
+    /// Workers currently listing a directory
+    busy: usize,
+    entries: Vec<FileEntry>,
+    cached_entries: usize,
+    unreadable: Vec<PathBuf>,
+    cache: HashMap<PathBuf, CachedDir>,
+    timed_out: bool,
+}
+
+struct Queue {
+    state: Mutex<QueueState>,
+    ready: Condvar,
+}
+
+impl Queue {
+    fn work(&self, walk: &Walk) {
+        loop {
+            let (dir, stamp) = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    if walk.start.elapsed() > BUDGET && !state.timed_out {
+                        state.timed_out = true;
+                        state.dirs.clear();
+                    }
+                    if let Some(next) = state.dirs.pop() { state.busy += 1; break next; }
+                    if state.busy == 0 { self.ready.notify_all(); return; }
+                    state = self.ready.wait(state).unwrap();
+                }
+            };
+            let listing = walk.list(&dir, stamp);
+            let mut state = self.state.lock().unwrap();
+            state.busy -= 1;
+            if listing.cached { state.cached_entries += listing.entries.len(); }
+            state.entries.extend(listing.entries);
+            if !state.timed_out { state.dirs.extend(listing.subdirs); }
+            if listing.denied { state.unreadable.push(dir.clone()); }
+            if let Some(store) = listing.store { state.cache.insert(dir, store); }
+            self.ready.notify_all();
+        }
+    }
+}
+
+impl Walk<'_> {
+    /// Entries of one directory and the subdirectories to descend into. The names come from the cache when the
+    /// directory is unchanged and its listing younger than [`CACHE_MAX_AGE`].
+    fn list(&self, dir: &Path, stamp: Stamp) -> Listing {
+        let cached = self.cache.and_then(|c| c.dirs.get(dir)).filter(|c| c.stamp == stamp && self.now.saturating_sub(c.scanned_at) < CACHE_MAX_AGE.as_secs());
+        if let Some(cached) = cached { return self.list_cached(dir, cached); }
+
+        let mut listing = Listing::default();
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) => {
+                trace::event("walk", || format!("{} failed: {}", dir.display(), e));
+                listing.denied = e.kind() == io::ErrorKind::PermissionDenied;
+                return listing;
+            }
+        };
+        let mut store = Some(CachedDir { stamp, scanned_at: self.now, names: Vec::new() });
+        for item in read_dir.flatten() {
+            match item.file_name().into_string() {
+                Ok(name) => { if let Some(store) = store.as_mut() { store.names.push(name); } }
+                Err(_) => store = None,
+            }
+            self.add(&mut listing, item.path(), stamp.dev);
+        }
+        listing.store = store;
+        listing
+    }
+
+    fn list_cached(&self, dir: &Path, cached: &CachedDir) -> Listing {
+        let mut listing = Listing { cached: true, store: Some(cached.clone()), ..Listing::default() };
+        for name in &cached.names { self.add(&mut listing, dir.join(name), cached.stamp.dev); }
+        listing
+    }
+
+    /// Reads the metadata of `path` into `listing`, unless it is on another device than its directory.
+    fn add(&self, listing: &mut Listing, path: PathBuf, dev: u64) {
+        // Entries may vanish while walking
+        let Ok(meta) = fs::symlink_metadata(&path) else { return };
+        // Another mount (walked as its own root or skipped) or an excluded tree
+        if meta.dev() != dev || self.excluded.contains(&path) { return; }
+        if meta.is_dir() { listing.subdirs.push((path.clone(), Stamp::of(&meta))); }
+        listing.entries.push(entry(path, &meta));
+    }
+}
+
+fn entry(path: PathBuf, meta: &fs::Metadata) -> FileEntry {
+    FileEntry { path, mode: meta.mode(), uid: meta.uid(), gid: meta.gid() }
+}
+
+/// Mounts to walk and mounts to skip, from `/proc/self/mountinfo`. The same filesystem mounted twice (bind mounts)
+/// is walked once.
+fn mounts() -> (Vec<Mount>, Vec<Mount>) {
+    parse_mounts(&trace::read_to_string("/proc/self/mountinfo").unwrap_or_default())
+}
+
+fn parse_mounts(content: &str) -> (Vec<Mount>, Vec<Mount>) {
+    let (mut walked, mut skipped) = (Vec::<Mount>::new(), Vec::<Mount>::new());
+    let mut seen = BTreeSet::new();
+    let mut all: Vec<(String, String, PathBuf, String)> = content
+        .lines()
+        .filter_map(|line| {
+            // ID PARENT MAJOR:MINOR ROOT MOUNTPOINT OPTIONS [OPTIONAL...] - FSTYPE SOURCE SUPER_OPTIONS
+            let (left, right) = line.split_once(" - ")?;
+            let fields: Vec<&str> = left.split_whitespace().collect();
+            let fstype = right.split_whitespace().next()?;
+            Some((fields.get(2)?.to_string(), unescape(fields.get(3)?), PathBuf::from(unescape(fields.get(4)?)), fstype.to_string()))
+        })
+        .collect();
+    // Parents before children, so a mount below a skipped one is skipped too
+    all.sort_by(|a, b| a.2.cmp(&b.2));
+    for (device, root, path, fstype) in all {
+        // Mounts below a skipped one are never reached, so they are not listed
+        if skipped.iter().any(|m| path.starts_with(&m.path)) { continue; }
+        let duplicate = !seen.insert((device, root));
+        if duplicate && walked.iter().any(|m| m.path == path) { continue; }
+        let mount = Mount { path, fstype };
+        let fstype = mount.fstype.as_str();
+        let excluded = SKIPPED_PATHS.iter().any(|p| mount.path.starts_with(p));
+        if duplicate || excluded || PSEUDO.contains(&fstype) || NETWORK.contains(&fstype) || IMAGES.contains(&fstype) {
+            // Mounted over a walked filesystem, which is then hidden
+            walked.retain(|m| m.path != mount.path);
+            skipped.push(mount);
+        } else {
+            walked.retain(|m| m.path != mount.path);
+            walked.push(mount);
+        }
+    }
+    if walked.is_empty() { walked.push(Mount { path: PathBuf::from("/"), fstype: "unknown".into() }); }
+    (walked, skipped)
+}
+
+/// mountinfo escapes spaces, tabs, newlines and backslashes as octal.
+fn unescape(field: &str) -> String {
+    field.replace("\\040", " ").replace("\\011", "\t").replace("\\012", "\n").replace("\\134", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn fixture(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vps-audit-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("bin")).unwrap();
+        fs::write(dir.join("bin/tool"), "").unwrap();
+        fs::set_permissions(dir.join("bin/tool"), fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    fn stamp(dir: &Path) -> Stamp {
+        Stamp::of(&fs::symlink_metadata(dir).unwrap())
+    }
+
+    /// Lists `dir` like one worker step and returns the new cache entry.
+    fn list(walk: &Walk, dir: &Path) -> (Listing, CachedDir) {
+        let mut listing = walk.list(dir, stamp(dir));
+        let store = listing.store.take().unwrap();
+        (listing, store)
+    }
+
+    fn walk(cache: Option<&ScanCache>, now: u64) -> Walk<'_> {
+        Walk { excluded: BTreeSet::new(), cache, now, start: Instant::now() }
+    }
+
+    fn mode_of(listing: &Listing, path: &Path) -> u32 {
+        listing.entries.iter().find(|e| e.path == path).unwrap().permissions()
+    }
+
+    #[test]
+    fn pseudo_temporary_and_repeated_mounts_are_skipped() {
+        let mountinfo = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+23 22 0:21 / /proc rw - proc proc rw
+24 22 0:30 / /tmp rw - tmpfs tmpfs rw
+25 22 8:2 / /home rw - ext4 /dev/sda2 rw
+26 22 8:3 / /mnt/backup rw - ext4 /dev/sda3 rw
+27 22 8:2 /srv /srv rw - ext4 /dev/sda2 rw
+28 22 8:2 / /home2 rw - ext4 /dev/sda2 rw
+29 22 0:40 / /var/lib/docker/overlay2/x/merged rw - overlay overlay rw
+30 22 8:17 / /media/my\\040usb rw - vfat /dev/sdb1 rw
+";
+        let (walked, skipped) = parse_mounts(mountinfo);
+        let paths = |mounts: &[Mount]| mounts.iter().map(|m| m.path.display().to_string()).collect::<BTreeSet<_>>();
+        assert_eq!(paths(&walked), BTreeSet::from(["/".into(), "/home".into(), "/srv".into()]));
+        assert_eq!(
+            paths(&skipped),
+            ["/home2", "/media/my usb", "/mnt/backup", "/proc", "/tmp", "/var/lib/docker/overlay2/x/merged"].into_iter().map(String::from).collect()
+        );
+    }
+
+    #[test]
+    fn cached_listing_still_sees_permission_changes() {
+        let root = fixture("chmod");
+        let bin = root.join("bin");
+        let (_, store) = list(&walk(None, unix_now()), &bin);
+        let cache = ScanCache { version: CACHE_VERSION, dirs: HashMap::from([(bin.clone(), store)]) };
+
+        // chmod does not touch the directory, so its listing comes from the cache
+        fs::set_permissions(bin.join("tool"), fs::Permissions::from_mode(0o4755)).unwrap();
+        let (listing, _) = list(&walk(Some(&cache), unix_now()), &bin);
+        assert!(listing.cached);
+        assert_eq!(mode_of(&listing, &bin.join("tool")), 0o4755);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn changed_or_old_directories_are_listed_again() {
+        let root = fixture("stale");
+        let bin = root.join("bin");
+        let now = unix_now();
+        let (_, store) = list(&walk(None, now), &bin);
+        let cache = ScanCache { version: CACHE_VERSION, dirs: HashMap::from([(bin.clone(), store)]) };
+        assert!(list(&walk(Some(&cache), now), &bin).0.cached);
+
+        // A listing older than the maximum age is not trusted
+        let (listing, _) = list(&walk(Some(&cache), now + CACHE_MAX_AGE.as_secs()), &bin);
+        assert!(!listing.cached);
+
+        // A new file changes the directory stamp
+        fs::write(bin.join("new"), "").unwrap();
+        let (listing, store) = list(&walk(Some(&cache), now), &bin);
+        assert!(!listing.cached);
+        assert!(listing.entries.iter().any(|e| e.path == bin.join("new")));
+        assert_eq!(store.names.len(), 2);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,18 +1,62 @@
+mod aggregate;
+mod bundle;
 mod model;
+mod notify;
 mod engine;
+mod exec;
 mod collectors;
+mod compliance;
+mod fsindex;
 mod checks;
+mod output;
+mod receiver;
+mod redact;
 mod report;
+mod sandbox;
+mod serve;
+mod sign;
+mod trace;
 
 use crate::engine::AuditEngine;
-use crate::report::{OutputFormat, Reporter};
-use clap::{Parser, ValueEnum};
+use crate::model::{AuditReport, Status};
+use crate::output::OutputTarget;
+use crate::redact::Redactor;
+use crate::report::text::{ColorChoice, GroupBy};
+use crate::report::{OutputFormat, Reporter, TextOptions};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
 
 #[derive(Parser, Debug)]
-#[command(name = "vps-audit", version, about = "Self-contained VPS security and health audit CLI")]
+#[command(name = "vps-audit", version, about = "Self-contained VPS security and health audit CLI", args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// Output format
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    audit: AuditArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the audit (default when no subcommand is given)
+    Audit(AuditArgs),
+    /// Serve /metrics (OpenMetrics), /report.json and /healthz over HTTP
+    Serve(ServeArgs),
+    /// Print the JSON Schema of the `--format json` report
+    Schema,
+    /// Verify the signature of a JSON report produced with --sign
+    Verify(VerifyArgs),
+    /// Explain why a check matters, what fixing it involves and what it expects; lists all checks without an id
+    Explain(ExplainArgs),
+    /// Fleet overview over JSON reports from many hosts
+    Aggregate(AggregateArgs),
+    /// Receive reports pushed with `--push`, keep per-host history in SQLite and serve a read-only overview
+    ServeCollector(ServeCollectorArgs),
+}
+
+#[derive(Args, Debug)]
+struct AuditArgs {
+    /// Output format (prometheus/openmetrics are suitable for the node_exporter textfile collector)
     #[arg(long, value_enum, default_value_t = FormatArg::Text)]
     format: FormatArg,
 
@@ -20,23 +64,272 @@ struct Cli {
     #[arg(long)]
     categories: Option<String>,
 
-    /// Show PASS and SKIP results too
+    /// Show PASS and SKIP results too (text output only)
     #[arg(long, default_value_t = false)]
     verbose: bool,
 
+    /// Also write the report to a file; repeatable. The format is inferred from the extension
+    /// (.txt, .json, .ndjson, .prom, .html, .sarif, .md, .xml) or given as <path>:<format>.
+    /// Files are written atomically with mode 0600; a failed write exits with code 3
+    #[arg(long = "output", short = 'o', value_name = "PATH[:FORMAT]")]
+    outputs: Vec<OutputTarget>,
+
+    /// Replace IP addresses, hostnames, local usernames and home paths with stable pseudonyms (ip-1, host-1, user-1)
+    /// in reasons, evidence and host facts, in every output format
+    #[arg(long, default_value_t = false)]
+    redact: bool,
+
+    /// Additional regex to redact; repeatable and implies --redact. With a capture group only the group is replaced
+    #[arg(long = "redact-pattern", value_name = "REGEX")]
+    redact_patterns: Vec<regex::Regex>,
+
+    /// Write an evidence bundle (tar.zst) with the JSON report, the collected facts, copies of every file and command
+    /// output the checks read and a manifest with SHA-256 hashes; a failed write exits with code 3
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["redact", "redact_patterns"])]
+    bundle: Option<std::path::PathBuf>,
+
+    /// Sign JSON reports with this ed25519 key (PKCS#8 PEM or base64 seed); check with `vps-audit verify`
+    #[arg(long, value_name = "KEY")]
+    sign: Option<std::path::PathBuf>,
+
+    /// Colorize text output: auto (only on a terminal, honoring NO_COLOR), always or never
+    #[arg(long, value_enum, default_value_t = ColorArg::Auto)]
+    color: ColorArg,
+
+    /// Group text output by check domain, primary category, or not at all
+    #[arg(long, value_enum, default_value_t = GroupByArg::Domain)]
+    group_by: GroupByArg,
+
+    /// Print one line per check in text output
+    #[arg(long, default_value_t = false)]
+    compact: bool,
+
+    /// Print a control-by-control compliance table for this framework instead of the check list
+    /// (text, json, markdown or html format)
+    #[arg(long, value_enum, value_name = "FRAMEWORK")]
+    report_by: Option<ReportByArg>,
+
+    /// Only include results with these statuses in the report (comma separated: pass, warn, fail, skip).
+    /// Counts and score still cover all checks. Not available with the prometheus and openmetrics formats
+    #[arg(long, value_delimiter = ',')]
+    status: Vec<Status>,
+
     /// Fail the process if any check FAILs (exit code 2) or WARNs (exit code 1)
     #[arg(long, default_value_t = false)]
     strict: bool,
 
+    /// Upload the JSON report to a `vps-audit serve-collector` at this URL; a failed upload exits with code 3
+    #[arg(long, value_name = "URL", requires = "token")]
+    push: Option<String>,
+
+    /// Bearer token for --push
+    #[arg(long)]
+    token: Option<String>,
+
+    /// Id the collector keeps this host's history under with --push; defaults to a hash of /etc/machine-id.
+    /// Signed reports bind the id to the signing key on first upload
+    #[arg(long, value_name = "ID", requires = "push")]
+    agent_id: Option<String>,
+
+    /// Send notifications about new failures to the webhook, SMTP and command sinks in this JSON file;
+    /// a failed notification exits with code 3
+    #[arg(long, value_name = "CONFIG")]
+    notify: Option<std::path::PathBuf>,
+
+    /// Never run external programs such as `sshd -T`; checks fall back to parsing configuration files
+    #[arg(long, default_value_t = false)]
+    no_exec: bool,
+
+    /// Read every directory again instead of reusing unchanged listings from the scan cache (the cache is still
+    /// updated). The cache only skips readdir; every file is stat'ed on each run either way
+    #[arg(long, default_value_t = false)]
+    full_scan: bool,
+
+    /// Confine the audit before it collects anything: the filesystem becomes read-only except the --output
+    /// directories and the scan cache directory (Landlock), network and other system calls checks do not need are
+    /// denied (seccomp) and only helpers such as sshd may be executed. Exits with code 3 when the kernel cannot enforce it
+    #[arg(long, default_value_t = false, conflicts_with_all = ["push", "notify"])]
+    sandbox: bool,
+
+    /// Log every file read, command (exit code, start of its output), timeout and step duration to stderr,
+    /// then the slowest steps
+    #[arg(long, default_value_t = false)]
+    trace: bool,
+
     /// Run non-interactively (disables wizard)
     #[arg(long, default_value_t = false)]
     non_interactive: bool,
 }
 
+#[derive(Args, Debug)]
+struct ServeCollectorArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:9478")]
+    listen: String,
+
+    /// SQLite database holding the report history (created if missing)
+    #[arg(long, default_value = "vps-audit.db")]
+    db: std::path::PathBuf,
+
+    /// Bearer token agents must present when pushing
+    #[arg(long)]
+    token: String,
+
+    /// Only accept reports signed with this ed25519 public key (see --sign)
+    #[arg(long)]
+    pubkey: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct VerifyArgs {
+    /// JSON report to verify
+    report: std::path::PathBuf,
+
+    /// Public key of the signer (SPKI PEM or base64)
+    #[arg(long)]
+    pubkey: std::path::PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct ExplainArgs {
+    /// Check id, e.g. ssh.password_auth
+    id: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct AggregateArgs {
+    /// JSON reports, one per host (glob patterns are expanded, e.g. 'reports/*.json')
+    #[arg(required = true)]
+    reports: Vec<std::path::PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = AggregateFormat::Text)]
+    format: AggregateFormat,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum AggregateFormat {
+    Text,
+    Json,
+    Html,
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:9477")]
+    listen: String,
+
+    /// Only run checks in these categories (comma separated)
+    #[arg(long)]
+    categories: Option<String>,
+
+    /// Maximum age of cached results; a scrape of older results starts a refresh in the background and still gets them
+    #[arg(long, default_value = "60s", value_parser = humantime::parse_duration)]
+    refresh: std::time::Duration,
+
+    /// Re-run the audit in the background every --refresh instead of on scrape
+    #[arg(long, default_value_t = false)]
+    schedule: bool,
+
+    /// Walk the filesystem for file checks (SUID scan) at most this often; refreshes in between reuse the last walk
+    #[arg(long, default_value = "1h", value_parser = humantime::parse_duration)]
+    rescan: std::time::Duration,
+
+    /// Redact /report.json like `audit --redact`
+    #[arg(long, default_value_t = false)]
+    redact: bool,
+
+    /// Additional regex to redact; repeatable and implies --redact
+    #[arg(long = "redact-pattern", value_name = "REGEX")]
+    redact_patterns: Vec<regex::Regex>,
+
+    /// Notify sinks from this JSON file after every audit run (best used with --schedule)
+    #[arg(long, value_name = "CONFIG")]
+    notify: Option<std::path::PathBuf>,
+
+    /// Never run external programs, like `audit --no-exec`
+    #[arg(long, default_value_t = false)]
+    no_exec: bool,
+
+    /// Ignore the scan cache, like `audit --full-scan`
+    #[arg(long, default_value_t = false)]
+    full_scan: bool,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
 enum FormatArg {
     Text,
     Json,
+    /// One JSON object per result as checks complete, then a summary record
+    Ndjson,
+    Prometheus,
+    #[value(name = "openmetrics")]
+    OpenMetrics,
+    Html,
+    Sarif,
+    Markdown,
+    /// JUnit XML
+    Junit,
+    /// `path:line: STATUS id: reason` for findings with a config file location
+    Locations,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ReportByArg {
+    /// CIS Benchmark (Debian/Ubuntu)
+    Cis,
+    /// NIST SP 800-53 Rev. 5
+    Nist,
+    /// ISO/IEC 27001:2022 Annex A
+    Iso27001,
+    /// BSI IT-Grundschutz
+    Bsi,
+}
+
+impl From<ReportByArg> for compliance::Framework {
+    fn from(value: ReportByArg) -> Self {
+        match value {
+            ReportByArg::Cis => compliance::Framework::Cis,
+            ReportByArg::Nist => compliance::Framework::Nist80053,
+            ReportByArg::Iso27001 => compliance::Framework::Iso27001,
+            ReportByArg::Bsi => compliance::Framework::Bsi,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ColorArg {
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<ColorArg> for ColorChoice {
+    fn from(value: ColorArg) -> Self {
+        match value {
+            ColorArg::Auto => ColorChoice::Auto,
+            ColorArg::Always => ColorChoice::Always,
+            ColorArg::Never => ColorChoice::Never,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum GroupByArg {
+    None,
+    Category,
+    Domain,
+}
+
+impl From<GroupByArg> for GroupBy {
+    fn from(value: GroupByArg) -> Self {
+        match value {
+            GroupByArg::None => GroupBy::None,
+            GroupByArg::Category => GroupBy::Category,
+            GroupByArg::Domain => GroupBy::Domain,
+        }
+    }
 }
 
 impl From<FormatArg> for OutputFormat {
@@ -44,17 +337,160 @@ impl From<FormatArg> for OutputFormat {
         match value {
             FormatArg::Text => OutputFormat::Text,
             FormatArg::Json => OutputFormat::Json,
+            FormatArg::Ndjson => OutputFormat::Ndjson,
+            FormatArg::Prometheus => OutputFormat::Prometheus,
+            FormatArg::OpenMetrics => OutputFormat::OpenMetrics,
+            FormatArg::Html => OutputFormat::Html,
+            FormatArg::Sarif => OutputFormat::Sarif,
+            FormatArg::Markdown => OutputFormat::Markdown,
+            FormatArg::Junit => OutputFormat::Junit,
+            FormatArg::Locations => OutputFormat::Locations,
         }
     }
 }
 
 fn main() {
     let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Audit(args)) => run_audit(args),
+        Some(Command::Serve(args)) => run_serve(args),
+        Some(Command::Schema) => println!("{}", Reporter::json_schema()),
+        Some(Command::Verify(args)) => run_verify(args),
+        Some(Command::Explain(args)) => run_explain(args),
+        Some(Command::Aggregate(args)) => run_aggregate(args),
+        Some(Command::ServeCollector(args)) => run_serve_collector(args),
+        None => run_audit(cli.audit),
+    }
+}
+
+fn parse_categories(value: Option<&String>) -> Option<Vec<String>> {
+    value.map(|s| s.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+}
+
+fn run_verify(args: VerifyArgs) {
+    let result = sign::load_verifying_key(&args.pubkey).and_then(|key| {
+        let json = std::fs::read_to_string(&args.report)?;
+        sign::verify_report(&json, &key)
+    });
+    match result {
+        Ok(()) => println!("{}: signature OK", args.report.display()),
+        Err(e) => {
+            eprintln!("{}: verification failed: {:#}", args.report.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_explain(args: ExplainArgs) {
+    let Some(id) = args.id else {
+        for check in checks::all() { println!("{:<28} {}", check.id(), check.title()); }
+        return;
+    };
+    let Some(check) = checks::find(&id) else {
+        let known: Vec<&str> = checks::all().iter().map(|c| c.id()).collect();
+        eprintln!("error: unknown check '{}'; known checks: {}", id, known.join(", "));
+        std::process::exit(2);
+    };
+    let stdout_is_terminal = std::io::IsTerminal::is_terminal(&std::io::stdout());
+    let options = TextOptions {
+        color: ColorChoice::Auto.enabled(),
+        width: if stdout_is_terminal { report::text::terminal_width() } else { None },
+        ..TextOptions::default()
+    };
+    print!("{}", report::text::explain(check.as_ref(), &options));
+}
+
+fn run_aggregate(args: AggregateArgs) {
+    let reports = aggregate::load_reports(&args.reports);
+    if reports.is_empty() {
+        eprintln!("error: no readable reports");
+        std::process::exit(3);
+    }
+    let fleet = aggregate::FleetReport::build(&reports);
+    match args.format {
+        AggregateFormat::Text => print!("{}", fleet.render_text()),
+        AggregateFormat::Json => println!("{}", fleet.render_json()),
+        AggregateFormat::Html => print!("{}", fleet.render_html()),
+    }
+}
+
+fn run_serve_collector(args: ServeCollectorArgs) {
+    let result = args
+        .pubkey
+        .as_deref()
+        .map(sign::load_verifying_key)
+        .transpose()
+        .and_then(|pubkey| {
+            let store = receiver::ReportStore::open(&args.db)?;
+            receiver::ReportReceiver::new(store, receiver::ReceiverOptions { listen: args.listen, token: args.token, pubkey }).run()
+        });
+    if let Err(e) = result {
+        eprintln!("error: {:#}", e);
+        std::process::exit(3);
+    }
+}
+
+fn load_notifier(path: &std::path::Path) -> notify::Notifier {
+    notify::Notifier::load(path).unwrap_or_else(|e| {
+        eprintln!("error: {:#}", e);
+        std::process::exit(3);
+    })
+}
+
+/// Post-processing applied to every report before it is printed or written: status filter, redaction, signature.
+struct ReportPipeline {
+    statuses: Vec<Status>,
+    redactor: Option<Redactor>,
+    signing_key: Option<ed25519_dalek::SigningKey>,
+}
+
+impl ReportPipeline {
+    fn apply(&mut self, report: &mut AuditReport) {
+        report.retain_statuses(&self.statuses);
+        // Pseudonyms are memoized, so results streamed before the report was finished keep the same names
+        if let Some(redactor) = self.redactor.as_mut() { redactor.redact_report(report); }
+        // Signing comes last so the signature covers exactly what gets printed or written
+        if let Some(key) = &self.signing_key { sign::sign_report(report, key); }
+    }
+}
 
-    let mut categories: Option<Vec<String>> = cli
-        .categories
-        .as_ref()
-        .map(|s| s.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect());
+fn run_serve(args: ServeArgs) {
+    if args.no_exec { exec::disable(); }
+    if args.full_scan { fsindex::ignore_cache(); }
+    fsindex::reuse_for(args.rescan);
+    let mut engine = AuditEngine::new(parse_categories(args.categories.as_ref()));
+    engine.register_default_checks();
+    let server = serve::AuditServer::new(engine, serve::ServeOptions {
+        listen: args.listen,
+        refresh: args.refresh,
+        schedule: args.schedule,
+        redact: (args.redact || !args.redact_patterns.is_empty()).then_some(args.redact_patterns),
+        notifier: args.notify.as_deref().map(load_notifier),
+    });
+    if let Err(e) = server.run() {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run_audit(cli: AuditArgs) {
+    if cli.report_by.is_some() && !matches!(cli.format, FormatArg::Text | FormatArg::Json | FormatArg::Markdown | FormatArg::Html) {
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, "--report-by supports --format text, json, markdown or html").exit();
+    }
+    let metrics = |format: OutputFormat| matches!(format, OutputFormat::Prometheus | OutputFormat::OpenMetrics);
+    if !cli.status.is_empty() && (metrics(cli.format.into()) || cli.outputs.iter().any(|t| metrics(t.format))) {
+        // A scrape must see every check; a filtered series would look like a check that disappeared
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, "--status cannot be combined with the prometheus or openmetrics formats").exit();
+    }
+    if cli.sandbox && !cli.non_interactive {
+        Cli::command().error(clap::error::ErrorKind::ArgumentConflict, "--sandbox requires --non-interactive").exit();
+    }
+    if cli.trace { trace::enable(); }
+    if cli.no_exec { exec::disable(); }
+    if cli.full_scan { fsindex::ignore_cache(); }
+    // Keeps every file read and command output from here on for the bundle
+    if cli.bundle.is_some() { trace::capture(); }
+    let mut categories = parse_categories(cli.categories.as_ref());
 
     if !cli.non_interactive {
         categories = interactive_select_categories(categories);
@@ -63,18 +499,117 @@ fn main() {
     let mut engine = AuditEngine::new(categories.clone());
     engine.register_default_checks();
 
-    let results = engine.run_all();
+    let color = ColorChoice::from(cli.color).enabled();
+    let reporter = Reporter::new(cli.verbose, cli.format.into()).with_text_options(TextOptions {
+        color,
+        group_by: cli.group_by.into(),
+        compact: cli.compact,
+        width: if std::io::IsTerminal::is_terminal(&std::io::stdout()) { report::text::terminal_width() } else { None },
+    });
+    let signing_key = cli.sign.as_deref().map(|path| {
+        sign::load_signing_key(path).unwrap_or_else(|e| {
+            eprintln!("error: {:#}", e);
+            std::process::exit(3);
+        })
+    });
+    let notifier = cli.notify.as_deref().map(load_notifier);
+    let mut pipeline = ReportPipeline {
+        statuses: cli.status.clone(),
+        redactor: (cli.redact || !cli.redact_patterns.is_empty()).then(|| Redactor::new(&cli.redact_patterns)),
+        signing_key,
+    };
+    if cli.sandbox {
+        let mut dirs: Vec<_> = cli.outputs.iter().map(|t| t.path.as_path()).chain(cli.bundle.as_deref()).map(|p| output::output_dir(p).to_path_buf()).collect();
+        dirs.extend(fsindex::create_cache_dir());
+        if let Err(e) = sandbox::apply(&dirs) {
+            eprintln!("error: sandbox: {:#}", e);
+            std::process::exit(3);
+        }
+    }
+    let (mut report, facts) = if cli.format == FormatArg::Ndjson {
+        // Stream results while the audit is still running so a killed run leaves partial output
+        engine.audit_with_facts(|r| {
+            if pipeline.statuses.is_empty() || pipeline.statuses.contains(&r.status) {
+                let mut r = r.clone();
+                if let Some(redactor) = pipeline.redactor.as_mut() { redactor.redact_result(&mut r); }
+                println!("{}", Reporter::ndjson_result(&r));
+            }
+        })
+    } else {
+        engine.audit_with_facts(|_| {})
+    };
+    if cli.trace {
+        eprintln!("Slowest steps:");
+        for step in report.slowest_steps(10).iter().filter(|s| s.duration_ms > 0) { eprintln!("  {:>7}ms  {}", step.duration_ms, step.name); }
+    }
+    // Strict mode looks at every result, not only the ones kept by --status
+    let has_fail = report.summary.fail > 0;
+    let has_warn = report.summary.warn > 0;
+    // Notification state tracks every issue under its real name and controls roll up every result; --status and
+    // --redact only shape the output
+    let unfiltered = (notifier.is_some() || cli.report_by.is_some()).then(|| report.clone());
+    pipeline.apply(&mut report);
+
+    if let Some(framework) = cli.report_by {
+        let results = unfiltered.as_ref().map_or(&report.results, |u| &u.results);
+        let compliance = compliance::ComplianceReport::new(&report, results, framework.into());
+        match cli.format {
+            FormatArg::Json => println!("{}", compliance.render_json()),
+            FormatArg::Markdown => print!("{}", compliance.render_markdown()),
+            FormatArg::Html => print!("{}", compliance.render_html()),
+            _ => print!("{}", compliance.render_text()),
+        }
+    } else if cli.format == FormatArg::Ndjson {
+        println!("{}", Reporter::ndjson_summary(&report));
+    } else {
+        reporter.print(&report);
+    }
+
+    let mut write_failed = false;
+    for target in &cli.outputs {
+        if let Err(e) = target.write(&reporter, &report) {
+            eprintln!("error: failed to write {}: {}", target.path.display(), e);
+            write_failed = true;
+        }
+    }
+
+    if let Some(path) = &cli.bundle {
+        let json = reporter.plain().with_format(OutputFormat::Json).render(&report);
+        if let Err(e) = bundle::write(path, &report, &json, &facts, trace::captured()) {
+            eprintln!("error: failed to write bundle {}: {:#}", path.display(), e);
+            write_failed = true;
+        }
+    }
 
-    let reporter = Reporter::new(cli.verbose, cli.format.into());
-    reporter.print(&results);
+    if let (Some(notifier), Some(unfiltered)) = (&notifier, &unfiltered) {
+        if let Err(e) = notifier.notify(unfiltered, pipeline.redactor.as_mut()) {
+            eprintln!("error: {:#}", e);
+            write_failed = true;
+        }
+    }
+
+    if let (Some(url), Some(token)) = (&cli.push, &cli.token) {
+        let json = reporter.plain().with_format(OutputFormat::Json).render(&report);
+        let result = cli
+            .agent_id
+            .clone()
+            .or_else(receiver::default_agent_id)
+            .ok_or_else(|| anyhow::anyhow!("/etc/machine-id is unavailable, pass --agent-id"))
+            .and_then(|agent| receiver::push(url, token, &agent, &json));
+        if let Err(e) = result {
+            eprintln!("error: push failed: {:#}", e);
+            write_failed = true;
+        }
+    }
 
     if !cli.non_interactive {
-        interactive_wizard(&results, &reporter, &mut engine);
+        interactive_wizard(report, &mut pipeline, &reporter, &mut engine);
     }
 
+    if write_failed {
+        std::process::exit(3);
+    }
     if cli.strict {
-        let has_fail = results.iter().any(|r| r.status.is_fail());
-        let has_warn = results.iter().any(|r| r.status.is_warn());
         if has_fail {
             std::process::exit(2);
         } else if has_warn {
@@ -101,13 +636,16 @@ fn interactive_select_categories(preset: Option<Vec<String>>) -> Option<Vec<Stri
     if selections.is_empty() { None } else { Some(selections.into_iter().map(|i| all[i].to_string()).collect()) }
 }
 
-fn interactive_wizard(results: &[crate::model::CheckResult], reporter: &Reporter, engine: &mut AuditEngine) {
+fn interactive_wizard(report: AuditReport, pipeline: &mut ReportPipeline, reporter: &Reporter, engine: &mut AuditEngine) {
     let theme = ColorfulTheme::default();
-    let mut current_results = results.to_vec();
+    let mut current = report;
     loop {
-        let (pass, warn, fail, skip) = crate::report::Reporter::counts(&current_results);
-        println!("Score: {} / 100", crate::report::Reporter::score(&current_results));
-        println!("PASS={}, WARN={}, FAIL={}, SKIP={}", pass, warn, fail, skip);
+        let summary = &current.summary;
+        println!("Score: {} / 100", summary.score);
+        println!("PASS={}, WARN={}, FAIL={}, SKIP={}", summary.pass, summary.warn, summary.fail, summary.skip);
+        if let Some((name, area)) = summary.weakest_area() {
+            println!("Weakest area: {} ({} / 100, FAIL={}, WARN={})", name, area.score.unwrap_or_default(), area.fail, area.warn);
+        }
         let options = vec![
             "View failures",
             "View warnings",
@@ -124,23 +662,29 @@ fn interactive_wizard(results: &[crate::model::CheckResult], reporter: &Reporter
             .unwrap_or(options.len() - 1);
         match choice {
             0 => {
-                for r in current_results.iter().filter(|r| r.status.is_fail()) {
+                for r in current.results.iter().filter(|r| r.status.is_fail()) {
                     println!("[FAIL] {}\n  {}\n  remediation: {}\n", r.title, r.reason, r.remediation.clone().unwrap_or_default());
                 }
             }
             1 => {
-                for r in current_results.iter().filter(|r| r.status.is_warn()) {
+                for r in current.results.iter().filter(|r| r.status.is_warn()) {
                     println!("[WARN] {}\n  {}\n  remediation: {}\n", r.title, r.reason, r.remediation.clone().unwrap_or_default());
                 }
             }
             2 => {
                 let path: String = Input::with_theme(&theme).with_prompt("Save report to path").default("vps-audit-report.txt".into()).interact_text().unwrap_or_else(|_| "vps-audit-report.txt".into());
-                let contents = reporter.render(&current_results);
-                if std::fs::write(&path, contents).is_ok() { println!("Saved to {}", path); } else { println!("Failed to save to {}", path); }
+                // Infer the format from the extension like --output does; fall back to the console format
+                let target = path.parse::<OutputTarget>().ok();
+                let result = match &target {
+                    Some(target) => target.write(reporter, &current),
+                    None => output::write_atomic(std::path::Path::new(&path), reporter.plain().render(&current).as_bytes()),
+                };
+                if result.is_ok() { println!("Saved to {}", path); } else { println!("Failed to save to {}", path); }
             }
             3 => {
-                current_results = engine.run_all();
-                reporter.print(&current_results);
+                current = engine.audit();
+                pipeline.apply(&mut current);
+                reporter.print(&current);
             }
             4 => {
                 let new_categories = interactive_select_categories(None);
@@ -1,6 +1,18 @@
+use crate::collectors::{Collectors, SystemInfo};
+use crate::compliance::Framework;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub mod evidence;
+
+pub use evidence::{human_bytes, Evidence, EvidenceView};
+
+/// Version of the JSON report layout. Bump on breaking changes to `AuditReport`.
+pub const REPORT_SCHEMA_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Status {
     Pass,
@@ -12,9 +24,31 @@ pub enum Status {
 impl Status {
     pub fn is_fail(&self) -> bool { matches!(self, Status::Fail) }
     pub fn is_warn(&self) -> bool { matches!(self, Status::Warn) }
+    pub fn as_str(&self) -> &'static str {
+        match self { Status::Pass => "PASS", Status::Warn => "WARN", Status::Fail => "FAIL", Status::Skip => "SKIP" }
+    }
+
+    /// Most severe of `statuses` (FAIL > WARN > PASS), ignoring SKIP; PASS when there is nothing else.
+    pub fn worst(statuses: impl IntoIterator<Item = Status>) -> Status {
+        let rank = |s: &Status| match s { Status::Fail => 2, Status::Warn => 1, _ => 0 };
+        statuses.into_iter().filter(|s| !matches!(s, Status::Skip)).max_by_key(rank).unwrap_or(Status::Pass)
+    }
+}
+
+impl std::str::FromStr for Status {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "PASS" => Ok(Status::Pass),
+            "WARN" => Ok(Status::Warn),
+            "FAIL" => Ok(Status::Fail),
+            "SKIP" => Ok(Status::Skip),
+            other => Err(format!("unknown status '{}' (expected pass, warn, fail or skip)", other)),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CheckResult {
     pub id: String,
     pub title: String,
@@ -22,14 +56,574 @@ pub struct CheckResult {
     pub status: Status,
     pub reason: String,
     pub remediation: Option<String>,
-    pub evidence: Option<serde_json::Value>,
+    pub evidence: Option<Evidence>,
+    /// Configuration lines the result is about, e.g. the directive to change
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Location>,
+    /// Individual items the check looked at (files, ports, users); `status` is derived from them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    /// What running as root would add to this result, set when it was skipped or limited by missing privileges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub needs_root: Option<String>,
+    /// Wall-clock time of the check itself, 0 when it was skipped before running
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Compliance controls covered by the check, by framework (`cis`, `nist-800-53`, `iso-27001`, `bsi`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub controls: BTreeMap<String, Vec<String>>,
+}
+
+impl CheckResult {
+    /// Result skeleton carrying the check's id, title, categories and controls; checks fill in the rest with
+    /// struct update syntax: `CheckResult { status, reason, .., ..CheckResult::for_check(self) }`.
+    pub fn for_check(check: &dyn AuditCheck) -> Self {
+        let mut controls: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (framework, control) in check.controls() {
+            controls.entry(framework.key().to_string()).or_default().push(control.to_string());
+        }
+        Self {
+            id: check.id().to_string(),
+            title: check.title().to_string(),
+            categories: check.categories().iter().map(|s| s.to_string()).collect(),
+            status: Status::Skip,
+            reason: String::new(),
+            remediation: None,
+            evidence: None,
+            locations: Vec::new(),
+            findings: Vec::new(),
+            needs_root: None,
+            duration_ms: 0,
+            controls,
+        }
+    }
+
+    /// SKIP result for `check` because `prerequisite` does not hold, `detail` says why.
+    pub fn prerequisite_not_met(check: &dyn AuditCheck, prerequisite: &Prerequisite, ctx: &Collectors, detail: &str) -> Self {
+        Self {
+            reason: format!("Prerequisite '{}' not met ({})", prerequisite.describe(), detail),
+            remediation: prerequisite.remediation(),
+            needs_root: prerequisite.root_would_add(ctx),
+            ..Self::for_check(check)
+        }
+    }
+
+    /// Check domain, i.e. the id prefix (`ssh` for `ssh.root_login`).
+    pub fn domain(&self) -> &str {
+        self.id.split('.').next().unwrap_or(&self.id)
+    }
+
+    /// WARN/FAIL findings, or the result itself when it is WARN/FAIL without a failing finding.
+    pub fn issues(&self) -> Vec<Issue<'_>> {
+        let mut issues: Vec<Issue> = self
+            .findings
+            .iter()
+            .filter(|f| f.status.is_fail() || f.status.is_warn())
+            .map(|f| Issue { id: &self.id, subject: Some(&f.subject), status: f.status, reason: &f.reason })
+            .collect();
+        if issues.is_empty() && (self.status.is_fail() || self.status.is_warn()) {
+            issues.push(Issue { id: &self.id, subject: None, status: self.status, reason: &self.reason });
+        }
+        issues
+    }
+}
+
+/// One item a check looked at, e.g. a file with the SUID bit or a listening port.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Finding {
+    /// What the finding is about: a path, `tcp/8080`, a user name
+    pub subject: String,
+    pub status: Status,
+    pub reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<Evidence>,
+}
+
+impl Finding {
+    pub fn new(subject: impl Into<String>, status: Status, reason: impl Into<String>) -> Self {
+        Self { subject: subject.into(), status, reason: reason.into(), evidence: None }
+    }
+}
+
+/// A WARN/FAIL item keyed by check id and subject, the unit notifications and collector regressions compare.
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue<'a> {
+    pub id: &'a str,
+    pub subject: Option<&'a str>,
+    pub status: Status,
+    pub reason: &'a str,
+}
+
+impl Issue<'_> {
+    /// `check.id` or `check.id:subject`
+    pub fn key(&self) -> String {
+        match self.subject {
+            Some(subject) => format!("{}:{}", self.id, subject),
+            None => self.id.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Issue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.status.as_str(), self.key(), self.reason)
+    }
+}
+
+/// Wall-clock time of one step of a run, a collector or a check.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StepTiming {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+/// Place in a configuration file a result refers to.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Location {
+    pub path: String,
+    /// 1-based; absent when the setting is missing and would be added to `path`
+    pub line: Option<u32>,
+    /// The line as written in the file
+    pub text: Option<String>,
+}
+
+impl Location {
+    pub fn file(path: &str) -> Self {
+        Self { path: path.to_string(), line: None, text: None }
+    }
+}
+
+impl std::fmt::Display for Location {
+    /// `path:line`, the form editors and terminals turn into links.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.path, line),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// Static documentation of a check, shown by `vps-audit explain` and in HTML/Markdown reports.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckMetadata {
+    /// Why the check matters
+    pub rationale: &'static str,
+    /// Side effects of fixing it, e.g. lockout risks
+    pub impact: &'static str,
+    /// Values or state that make the check pass
+    pub expected: &'static str,
+    pub references: &'static [&'static str],
+}
+
+/// Condition that must hold before a check can produce a meaningful result; otherwise it is skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prerequisite {
+    /// Another check ran first and passed or warned, i.e. found what it was looking for
+    Check(&'static str),
+    /// OpenSSH server installed and its effective configuration readable
+    SshdConfig,
+    /// Running as root, or with CAP_DAC_READ_SEARCH, so files only root can read are readable
+    Root,
+    /// Not running inside a container, where host-level state is not visible
+    NotContainer,
+}
+
+impl Prerequisite {
+    pub fn describe(&self) -> String {
+        match self {
+            Prerequisite::Check(id) => id.to_string(),
+            Prerequisite::SshdConfig => "openssh-server installed".into(),
+            Prerequisite::Root => "running as root".into(),
+            Prerequisite::NotContainer => "not in a container".into(),
+        }
+    }
+
+    /// Why the prerequisite does not hold, `None` when it does. `results` holds the checks that already ran.
+    pub fn unmet(&self, ctx: &Collectors, results: &BTreeMap<&str, CheckResult>) -> Option<String> {
+        match self {
+            Prerequisite::Check(id) => match results.get(id) {
+                Some(r) if matches!(r.status, Status::Pass | Status::Warn) => None,
+                Some(r) => Some(format!("{}: {}", r.status.as_str(), r.reason)),
+                None if crate::checks::find(id).is_none() => Some("unknown check".into()),
+                None => Some("did not run, prerequisites form a cycle".into()),
+            },
+            Prerequisite::SshdConfig => match &ctx.sshd {
+                Some(sshd) if sshd.ok => None,
+                Some(_) if !ctx.privileges.can_read_all() => Some("sshd -T needs root and sshd_config is not readable".into()),
+                Some(sshd) => Some(format!("unable to obtain sshd config: {}", sshd.stderr.as_deref().unwrap_or_default().trim())),
+                None => Some("sshd_config not found".into()),
+            },
+            Prerequisite::Root if ctx.privileges.can_read_all() => None,
+            Prerequisite::Root => match ctx.privileges.euid {
+                Some(0) => Some("root without CAP_DAC_READ_SEARCH".into()),
+                Some(euid) => Some(format!("effective uid is {}", euid)),
+                None => Some("effective uid unknown".into()),
+            },
+            Prerequisite::NotContainer => ctx.container.as_ref().map(|c| format!("running in {}", c)),
+        }
+    }
+
+    /// What a root run would provide when it makes the prerequisite hold, for [`PrivilegeSummary::root_would_add`].
+    fn root_would_add(&self, ctx: &Collectors) -> Option<String> {
+        match self {
+            Prerequisite::Root => Some("read access to files only root can read, so the check can run".into()),
+            Prerequisite::SshdConfig if ctx.sshd.as_ref().is_some_and(|s| !s.ok) && !ctx.privileges.can_read_all() => {
+                Some("the sshd configuration (sshd -T or a readable sshd_config), so the check can run".into())
+            }
+            Prerequisite::Check(_) | Prerequisite::NotContainer | Prerequisite::SshdConfig => None,
+        }
+    }
+
+    fn remediation(&self) -> Option<String> {
+        match self {
+            Prerequisite::Check(_) | Prerequisite::NotContainer => None,
+            Prerequisite::SshdConfig => Some("Ensure OpenSSH server is installed and accessible".into()),
+            Prerequisite::Root => Some("Re-run vps-audit as root (sudo vps-audit)".into()),
+        }
+    }
 }
 
 pub trait AuditCheck: Send + Sync {
     fn id(&self) -> &'static str;
     fn title(&self) -> &'static str;
     fn categories(&self) -> &'static [&'static str];
+    /// Compliance controls this check provides evidence for
+    fn controls(&self) -> &'static [(Framework, &'static str)] { &[] }
+    fn metadata(&self) -> CheckMetadata { CheckMetadata::default() }
+    /// Conditions checked by the engine before `run`; checks named here run first
+    fn prerequisites(&self) -> &'static [Prerequisite] { &[] }
+    /// Reads `Collectors::files`; the filesystem is only walked when a check that runs says so
+    fn uses_file_index(&self) -> bool { false }
     fn run(&self, ctx: &crate::collectors::Collectors) -> CheckResult;
 }
 
+/// Top-level JSON report. Counts and score always cover every check that ran;
+/// `results` only contains the statuses selected by `filters.statuses`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AuditReport {
+    pub schema_version: u32,
+    pub tool: ToolInfo,
+    /// RFC 3339 timestamp of when the audit started
+    pub generated_at: String,
+    pub duration_ms: u64,
+    pub host: SystemInfo,
+    pub metrics: HostMetrics,
+    pub filters: ReportFilters,
+    pub summary: Summary,
+    /// Privileges the audit ran with and what a root run would add
+    #[serde(default)]
+    pub privileges: PrivilegeSummary,
+    pub results: Vec<CheckResult>,
+    /// Restrictions the audit ran under (`--sandbox`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxSummary>,
+    /// How long each collector took; check durations are in `results`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collector_timings: Vec<StepTiming>,
+    /// Detached signature over the canonical JSON of this report without this field (`--sign`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ReportSignature>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SandboxSummary {
+    /// Landlock ABI enforced by the kernel; rights newer than it are not restricted
+    pub landlock_abi: u8,
+    /// System calls the seccomp filter allows; all others fail with EPERM
+    pub allowed_syscalls: usize,
+    /// Directories the process could write to (`--output`); everything else was read-only
+    pub writable: Vec<String>,
+    /// Programs the process could execute
+    pub executable: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ReportSignature {
+    /// Always `ed25519`
+    pub algorithm: String,
+    /// Base64 encoded 32 byte public key of the signer
+    pub public_key: String,
+    /// Base64 encoded 64 byte signature over the compact, key-sorted JSON of the report without `signature`
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PrivilegeSummary {
+    pub euid: Option<u32>,
+    /// Effective capabilities, `null` when unknown
+    pub capabilities: Option<Vec<String>>,
+    /// Check id -> what a root run would add, covering every check that ran (not only the `--status` ones)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub root_would_add: BTreeMap<String, String>,
+}
+
+impl PrivilegeSummary {
+    /// Why the audit could not read everything, for the heading above [`PrivilegeSummary::root_would_add`].
+    pub fn limitation(&self) -> String {
+        match self.euid {
+            Some(0) => "The audit ran as root without CAP_DAC_READ_SEARCH or CAP_DAC_OVERRIDE (as in some containers)".into(),
+            Some(uid) => format!("The audit ran as uid {}, not as root", uid),
+            None => "The audit ran with unknown privileges".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ToolInfo {
+    pub name: String,
+    pub version: String,
+}
+
+/// Raw numeric host facts, also exported as Prometheus gauges.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HostMetrics {
+    pub disk_used_percent: f64,
+    pub memory_used_percent: f64,
+    pub load_ratio: f64,
+    pub listening_ports_total: usize,
+    pub listening_ports_public: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ReportFilters {
+    /// Categories the checks were restricted to, `null` when all categories ran
+    pub categories: Option<Vec<String>>,
+    /// Statuses kept in `results`, `null` when all results are included
+    pub statuses: Option<Vec<Status>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Summary {
+    pub pass: usize,
+    pub warn: usize,
+    pub fail: usize,
+    pub skip: usize,
+    pub score: u32,
+    /// Sub-scores per category (security, performance, ...); a check counts towards each of its categories
+    pub categories: BTreeMap<String, AreaScore>,
+    /// Sub-scores per check domain, the id prefix (ssh, system, policy, ...)
+    pub domains: BTreeMap<String, AreaScore>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AreaScore {
+    /// `null` when every check in the area was skipped
+    pub score: Option<u32>,
+    pub pass: usize,
+    pub warn: usize,
+    pub fail: usize,
+    pub skip: usize,
+}
+
+impl Summary {
+    pub fn new(results: &[CheckResult]) -> Self {
+        let (pass, warn, fail, skip) = crate::report::Reporter::counts(results);
+        let score = crate::report::Reporter::score(results);
+        let mut categories: BTreeMap<String, Vec<&CheckResult>> = BTreeMap::new();
+        let mut domains: BTreeMap<String, Vec<&CheckResult>> = BTreeMap::new();
+        for r in results {
+            for c in &r.categories { categories.entry(c.to_lowercase()).or_default().push(r); }
+            domains.entry(r.domain().to_string()).or_default().push(r);
+        }
+        Self { pass, warn, fail, skip, score, categories: area_scores(categories), domains: area_scores(domains) }
+    }
+
+    /// Category or domain with the lowest score, ignoring areas where every check was skipped.
+    pub fn weakest_area(&self) -> Option<(String, &AreaScore)> {
+        let categories = self.categories.iter().map(|(name, area)| (format!("category {}", name), area));
+        let domains = self.domains.iter().map(|(name, area)| (format!("domain {}", name), area));
+        categories
+            .chain(domains)
+            .filter(|(_, area)| area.score.is_some())
+            .min_by_key(|(_, area)| area.score)
+    }
+}
+
+fn area_scores(groups: BTreeMap<String, Vec<&CheckResult>>) -> BTreeMap<String, AreaScore> {
+    groups
+        .into_iter()
+        .map(|(name, results)| {
+            let (pass, warn, fail, skip) = crate::report::Reporter::counts(results.iter().copied());
+            let score = (skip < results.len()).then(|| crate::report::Reporter::score(results.iter().copied()));
+            (name, AreaScore { score, pass, warn, fail, skip })
+        })
+        .collect()
+}
+
+impl AuditReport {
+    pub fn new(facts: &Collectors, results: Vec<CheckResult>, started: SystemTime, duration: Duration, categories: Option<Vec<String>>) -> Self {
+        Self {
+            schema_version: REPORT_SCHEMA_VERSION,
+            tool: ToolInfo { name: env!("CARGO_PKG_NAME").into(), version: env!("CARGO_PKG_VERSION").into() },
+            generated_at: humantime::format_rfc3339_seconds(started).to_string(),
+            duration_ms: duration.as_millis() as u64,
+            host: facts.system.clone(),
+            metrics: HostMetrics {
+                disk_used_percent: facts.disk.used_pct(),
+                memory_used_percent: facts.memory.used_pct(),
+                load_ratio: facts.load_ratio(),
+                listening_ports_total: facts.listening_ports.len(),
+                listening_ports_public: facts.public_listening_ports(),
+            },
+            filters: ReportFilters { categories, statuses: None },
+            summary: Summary::new(&results),
+            privileges: PrivilegeSummary {
+                euid: facts.privileges.euid,
+                capabilities: facts.privileges.capabilities.clone(),
+                root_would_add: results.iter().filter_map(|r| Some((r.id.clone(), r.needs_root.clone()?))).collect(),
+            },
+            results,
+            sandbox: crate::sandbox::status(),
+            collector_timings: facts.timings.clone(),
+            signature: None,
+        }
+    }
+
+    /// The `n` slowest collectors and checks, slowest first.
+    pub fn slowest_steps(&self, n: usize) -> Vec<StepTiming> {
+        let collectors = self.collector_timings.iter().map(|t| StepTiming { name: format!("collector {}", t.name), duration_ms: t.duration_ms });
+        let checks = self.results.iter().map(|r| StepTiming { name: format!("check {}", r.id), duration_ms: r.duration_ms });
+        let mut steps: Vec<StepTiming> = collectors.chain(checks).collect();
+        steps.sort_by_key(|s| std::cmp::Reverse(s.duration_ms));
+        steps.truncate(n);
+        steps
+    }
+
+    /// Keeps only results with one of `statuses`. The summary is left untouched.
+    pub fn retain_statuses(&mut self, statuses: &[Status]) {
+        if statuses.is_empty() { return; }
+        self.results.retain(|r| statuses.contains(&r.status));
+        self.filters.statuses = Some(statuses.to_vec());
+    }
+}
+
+/// Report with one result per status, shared by unit tests that need a whole `AuditReport`.
+#[cfg(test)]
+pub(crate) fn sample_report() -> AuditReport {
+    let result = |id: &str, status: Status, reason: &str| CheckResult {
+        id: id.into(),
+        title: id.into(),
+        categories: vec!["Security".into()],
+        status,
+        reason: reason.into(),
+        remediation: None,
+        evidence: None,
+        locations: Vec::new(),
+        findings: Vec::new(),
+        needs_root: None,
+        duration_ms: 3,
+        controls: BTreeMap::new(),
+    };
+    let results = vec![
+        result("ssh.root_login", Status::Fail, "PermitRootLogin yes"),
+        result("ssh.password_auth", Status::Pass, "PasswordAuthentication no"),
+        result("system.disk_usage", Status::Warn, "Disk usage 81% on /home/alice"),
+    ];
+    AuditReport {
+        schema_version: REPORT_SCHEMA_VERSION,
+        tool: ToolInfo { name: "vps-audit".into(), version: "test".into() },
+        generated_at: "2026-01-01T00:00:00Z".into(),
+        duration_ms: 12,
+        host: SystemInfo {
+            hostname: Some("web-1.example.com".into()),
+            os_pretty_name: Some("Debian GNU/Linux 12 (bookworm)".into()),
+            kernel_version: Some("6.1.0".into()),
+            uptime_seconds: 3600,
+            total_memory_bytes: 2 << 30,
+            total_swap_bytes: 0,
+            load_average_1m: Some(0.37),
+            cpu_cores: 2,
+        },
+        metrics: HostMetrics {
+            disk_used_percent: 81.25,
+            memory_used_percent: 11.592373816040913,
+            load_ratio: 0.185,
+            listening_ports_total: 2,
+            listening_ports_public: 1,
+        },
+        filters: ReportFilters::default(),
+        summary: Summary::new(&results),
+        privileges: PrivilegeSummary::default(),
+        results,
+        sandbox: None,
+        collector_timings: Vec::new(),
+        signature: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::sample_collectors;
+
+    #[test]
+    fn envelope_counts_results_and_copies_host_facts() {
+        let report = AuditReport::new(&sample_collectors(), sample_report().results, SystemTime::UNIX_EPOCH, Duration::from_millis(5), None);
+        assert_eq!(report.generated_at, "1970-01-01T00:00:00Z");
+        assert_eq!((report.summary.pass, report.summary.warn, report.summary.fail, report.summary.score), (1, 1, 1, 50));
+        assert_eq!(report.metrics.disk_used_percent, 60.0);
+        assert_eq!(report.host.hostname.as_deref(), Some("web-1.example.com"));
+    }
+
+    #[test]
+    fn areas_are_scored_per_lowercase_category_and_domain() {
+        let mut results = sample_report().results;
+        results.push(CheckResult { id: "ssh.port".into(), status: Status::Fail, ..results[0].clone() });
+        results.push(CheckResult { id: "firewall.presence".into(), status: Status::Skip, categories: vec!["Network".into()], ..results[0].clone() });
+        let summary = Summary::new(&results);
+        assert_eq!(summary.domains["ssh"].score, Some(33));
+        assert_eq!(summary.domains["system"].score, Some(50));
+        assert_eq!(summary.domains["firewall"].score, None);
+        assert_eq!((summary.categories["security"].fail, summary.categories["security"].score), (2, Some(38)));
+        assert_eq!(summary.weakest_area().map(|(name, _)| name), Some("domain ssh".to_string()));
+    }
+
+    #[test]
+    fn status_filter_keeps_the_summary() {
+        let mut report = sample_report();
+        report.retain_statuses(&[Status::Fail]);
+        assert_eq!(report.results.len(), 1);
+        assert_eq!((report.summary.pass, report.summary.warn, report.summary.fail), (1, 1, 1));
+        assert_eq!(report.filters.statuses, Some(vec![Status::Fail]));
+    }
 
+    #[test]
+    fn prerequisites_explain_why_they_are_unmet() {
+        let mut ctx = sample_collectors();
+        let none = BTreeMap::new();
+        assert_eq!(Prerequisite::Root.unmet(&ctx, &none).as_deref(), Some("effective uid is 1000"));
+        assert_eq!(Prerequisite::SshdConfig.unmet(&ctx, &none).as_deref(), Some("sshd_config not found"));
+        assert_eq!(Prerequisite::NotContainer.unmet(&ctx, &none), None);
+        assert_eq!(Prerequisite::Check("no.such_check").unmet(&ctx, &none).as_deref(), Some("unknown check"));
+        assert_eq!(Prerequisite::Check("ssh.root_login").unmet(&ctx, &none).as_deref(), Some("did not run, prerequisites form a cycle"));
+
+        ctx.privileges.capabilities = Some(vec!["cap_dac_read_search".into()]);
+        ctx.container = Some("docker".into());
+        assert_eq!(Prerequisite::Root.unmet(&ctx, &none), None);
+        assert_eq!(Prerequisite::NotContainer.unmet(&ctx, &none).as_deref(), Some("running in docker"));
+    }
+
+    #[test]
+    fn root_notes_say_what_root_would_provide() {
+        let mut ctx = sample_collectors();
+        assert_eq!(Prerequisite::Root.root_would_add(&ctx).as_deref(), Some("read access to files only root can read, so the check can run"));
+        assert_eq!(Prerequisite::SshdConfig.root_would_add(&ctx), None, "no sshd installed, root does not help");
+        ctx.privileges.capabilities = Some(vec!["cap_dac_read_search".into()]);
+        assert_eq!(Prerequisite::NotContainer.root_would_add(&ctx), None);
+
+        let mut privileges = PrivilegeSummary { euid: Some(0), ..PrivilegeSummary::default() };
+        assert!(privileges.limitation().starts_with("The audit ran as root without CAP_DAC_READ_SEARCH"));
+        privileges.euid = Some(1000);
+        assert_eq!(privileges.limitation(), "The audit ran as uid 1000, not as root");
+    }
+
+    #[test]
+    fn check_prerequisite_holds_for_pass_and_warn() {
+        let ctx = sample_collectors();
+        let report = sample_report();
+        let results: BTreeMap<&str, CheckResult> = report.results.iter().map(|r| (r.id.as_str(), r.clone())).collect();
+        assert_eq!(Prerequisite::Check("ssh.password_auth").unmet(&ctx, &results), None);
+        assert_eq!(Prerequisite::Check("system.disk_usage").unmet(&ctx, &results), None);
+        assert_eq!(Prerequisite::Check("ssh.root_login").unmet(&ctx, &results).as_deref(), Some("FAIL: PermitRootLogin yes"));
+    }
+}
@@ -0,0 +1,381 @@
+use super::Location;
+use crate::collectors::{ListeningPort, MountUsage};
+use crate::exec::Invocation;
+use crate::fsindex::Mount;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What a verdict was based on, one shape per check; `kind` names the shape in JSON.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Evidence {
+    SshdSetting(SshdSettingEvidence),
+    ListeningPorts(ListeningPortsEvidence),
+    SuidScan(SuidScanEvidence),
+    FileMode(FileModeEvidence),
+    FirewallTools(FirewallToolsEvidence),
+    NftablesPolicy(NftablesPolicyEvidence),
+    RebootRequired(RebootRequiredEvidence),
+    DiskUsage(DiskUsageEvidence),
+    MemoryUsage(MemoryUsageEvidence),
+    Load(LoadEvidence),
+    SudoLogging(SudoLoggingEvidence),
+    PasswordPolicy(PasswordPolicyEvidence),
+    /// Evidence without a `kind`, from reports of schema version 1; not part of the schema
+    #[serde(untagged)]
+    #[schemars(skip)]
+    Other(serde_json::Value),
+}
+
+impl Evidence {
+    pub fn view(&self) -> &dyn EvidenceView {
+        match self {
+            Evidence::SshdSetting(e) => e,
+            Evidence::ListeningPorts(e) => e,
+            Evidence::SuidScan(e) => e,
+            Evidence::FileMode(e) => e,
+            Evidence::FirewallTools(e) => e,
+            Evidence::NftablesPolicy(e) => e,
+            Evidence::RebootRequired(e) => e,
+            Evidence::DiskUsage(e) => e,
+            Evidence::MemoryUsage(e) => e,
+            Evidence::Load(e) => e,
+            Evidence::SudoLogging(e) => e,
+            Evidence::PasswordPolicy(e) => e,
+            Evidence::Other(e) => e,
+        }
+    }
+}
+
+/// How reporters show evidence: labelled values, plus a table for lists of items (ports, filesystems).
+pub trait EvidenceView {
+    fn fields(&self) -> Vec<(&str, String)>;
+    fn table(&self) -> Option<EvidenceTable> { None }
+}
+
+pub struct EvidenceTable {
+    pub columns: &'static [&'static str],
+    pub rows: Vec<Vec<String>>,
+}
+
+/// `yes`/`no` for boolean evidence fields.
+pub fn yes_no(value: bool) -> String {
+    if value { "yes".into() } else { "no".into() }
+}
+
+/// Byte counts in IEC units, e.g. `1.5 GiB`.
+pub fn human_bytes(bytes: u64) -> String {
+    // Simple IEC units
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut n = bytes as f64;
+    let mut idx = 0;
+    while n >= 1024.0 && idx < UNITS.len() - 1 {
+        n /= 1024.0;
+        idx += 1;
+    }
+    format!("{:.1} {}", n, UNITS[idx])
+}
+
+impl EvidenceView for serde_json::Value {
+    fn fields(&self) -> Vec<(&str, String)> {
+        let plain = |v: &serde_json::Value| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string());
+        match self {
+            serde_json::Value::Object(map) => map.iter().map(|(k, v)| (k.as_str(), plain(v))).collect(),
+            other => vec![("value", plain(other))],
+        }
+    }
+}
+
+/// The sshd setting a result is based on and how the configuration was obtained.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SshdSettingEvidence {
+    /// Lowercase keyword as printed by `sshd -T`
+    pub setting: String,
+    /// Effective value, the sshd default when the setting is absent
+    pub value: String,
+    /// `sshd -T`, or the config file that was parsed instead
+    pub source: String,
+    /// The `sshd -T` run, also when it failed and the files were parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Invocation>,
+}
+
+impl EvidenceView for SshdSettingEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        let mut fields = vec![(self.setting.as_str(), self.value.clone()), ("source", self.source.clone())];
+        if let Some(command) = &self.command {
+            let exit = command.exit_code.map(|c| format!("exit {}", c)).unwrap_or_else(|| "killed".into());
+            fields.push(("command", format!("{} {} ({}, {} ms)", command.program, command.args.join(" "), exit, command.duration_ms)));
+        }
+        fields
+    }
+}
+
+/// Every listening socket, local ones included.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListeningPortsEvidence {
+    pub ports: Vec<PortEvidence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PortEvidence {
+    pub port: u16,
+    /// `tcp`, `tcp6`, `udp` or `udp6`
+    pub proto: String,
+    /// Bound to a wildcard or non-loopback address
+    pub public: bool,
+}
+
+impl From<&ListeningPort> for PortEvidence {
+    fn from(p: &ListeningPort) -> Self {
+        Self { port: p.port, proto: p.proto.to_string(), public: p.public }
+    }
+}
+
+impl EvidenceView for ListeningPortsEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![("ports", self.ports.len().to_string())]
+    }
+    fn table(&self) -> Option<EvidenceTable> {
+        let rows = self.ports.iter().map(|p| vec![p.proto.clone(), p.port.to_string(), yes_no(p.public)]).collect();
+        (!self.ports.is_empty()).then_some(EvidenceTable { columns: &["proto", "port", "public"], rows })
+    }
+}
+
+/// What the filesystem scan covered.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SuidScanEvidence {
+    pub files_scanned: usize,
+    /// Share of entries found by listing their directory in this run rather than from the scan cache
+    pub fresh_percent: u64,
+    /// Mounted filesystems, walked or skipped
+    pub filesystems: Vec<FilesystemEvidence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FilesystemEvidence {
+    pub path: String,
+    pub fstype: String,
+    /// `false` for pseudo, network and image filesystems and repeated bind mounts
+    pub scanned: bool,
+}
+
+impl FilesystemEvidence {
+    pub fn new(mount: &Mount, scanned: bool) -> Self {
+        Self { path: mount.path.display().to_string(), fstype: mount.fstype.clone(), scanned }
+    }
+}
+
+impl EvidenceView for SuidScanEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![("files scanned", self.files_scanned.to_string()), ("fresh", format!("{}%", self.fresh_percent))]
+    }
+    fn table(&self) -> Option<EvidenceTable> {
+        let rows = self.filesystems.iter().map(|f| vec![f.path.clone(), f.fstype.clone(), yes_no(f.scanned)]).collect();
+        (!self.filesystems.is_empty()).then_some(EvidenceTable { columns: &["filesystem", "type", "scanned"], rows })
+    }
+}
+
+/// Permissions and owner of a SUID file.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FileModeEvidence {
+    /// Permission bits in octal, e.g. `4755`
+    pub mode: String,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl EvidenceView for FileModeEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![("mode", self.mode.clone()), ("uid", self.uid.to_string()), ("gid", self.gid.to_string())]
+    }
+}
+
+/// Firewall tooling found by its configuration files or systemd units.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FirewallToolsEvidence {
+    pub nftables: bool,
+    pub ufw: bool,
+    pub iptables: bool,
+}
+
+impl EvidenceView for FirewallToolsEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![("nftables", yes_no(self.nftables)), ("ufw", yes_no(self.ufw)), ("iptables", yes_no(self.iptables))]
+    }
+}
+
+/// What the nftables configuration files contain.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NftablesPolicyEvidence {
+    pub input_chain: bool,
+    pub default_drop: bool,
+    /// An SSH or established/related accept rule
+    pub has_accept_examples: bool,
+}
+
+impl EvidenceView for NftablesPolicyEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![("input chain", yes_no(self.input_chain)), ("default drop", yes_no(self.default_drop)), ("accept rules", yes_no(self.has_accept_examples))]
+    }
+}
+
+/// The marker file package managers create when an update needs a reboot.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RebootRequiredEvidence {
+    pub marker: String,
+    pub present: bool,
+}
+
+impl EvidenceView for RebootRequiredEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![("marker", self.marker.clone()), ("present", yes_no(self.present))]
+    }
+}
+
+/// Space on all mounted disks together, and per mount.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiskUsageEvidence {
+    pub used_percent: f64,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub mounts: Vec<MountUsageEvidence>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MountUsageEvidence {
+    pub mount_point: String,
+    pub fstype: String,
+    pub used_percent: f64,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl From<&MountUsage> for MountUsageEvidence {
+    fn from(m: &MountUsage) -> Self {
+        Self {
+            mount_point: m.mount_point.clone(),
+            fstype: m.fstype.clone(),
+            used_percent: m.used_pct(),
+            total_bytes: m.total_bytes,
+            available_bytes: m.available_bytes,
+        }
+    }
+}
+
+impl EvidenceView for DiskUsageEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![
+            ("used", format!("{:.0}%", self.used_percent)),
+            ("total", human_bytes(self.total_bytes)),
+            ("available", human_bytes(self.available_bytes)),
+        ]
+    }
+    fn table(&self) -> Option<EvidenceTable> {
+        let rows = self
+            .mounts
+            .iter()
+            .map(|m| vec![m.mount_point.clone(), m.fstype.clone(), format!("{:.0}%", m.used_percent), human_bytes(m.total_bytes), human_bytes(m.available_bytes)])
+            .collect();
+        (!self.mounts.is_empty()).then_some(EvidenceTable { columns: &["mount", "type", "used", "total", "available"], rows })
+    }
+}
+
+/// Memory in use, with available memory as reported by the kernel (MemAvailable).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryUsageEvidence {
+    pub used_percent: f64,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl EvidenceView for MemoryUsageEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![
+            ("used", format!("{:.0}%", self.used_percent)),
+            ("total", human_bytes(self.total_bytes)),
+            ("available", human_bytes(self.available_bytes)),
+        ]
+    }
+}
+
+/// 1-minute load average against the number of CPU cores.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LoadEvidence {
+    /// `null` when /proc/loadavg is unreadable
+    pub load_average_1m: Option<f64>,
+    pub cpu_cores: usize,
+    /// Load per core, the value the thresholds apply to
+    pub load_ratio: f64,
+}
+
+impl EvidenceView for LoadEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![
+            ("load (1m)", self.load_average_1m.map(|l| format!("{:.2}", l)).unwrap_or_else(|| "unknown".into())),
+            ("cores", self.cpu_cores.to_string()),
+            ("ratio", format!("{:.2}", self.load_ratio)),
+        ]
+    }
+}
+
+/// The sudoers `Defaults logfile` directive, after following includes.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SudoLoggingEvidence {
+    /// Path sudo logs to, `null` when no directive sets one
+    pub logfile: Option<String>,
+    /// The directive as written, `null` when there is none
+    pub source: Option<Location>,
+    /// Lines read from sudoers and its includes
+    pub lines_read: usize,
+}
+
+impl EvidenceView for SudoLoggingEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![
+            ("logfile", self.logfile.clone().unwrap_or_else(|| "not set".into())),
+            ("source", self.source.as_ref().map(Location::to_string).unwrap_or_else(|| "none".into())),
+            ("lines read", self.lines_read.to_string()),
+        ]
+    }
+}
+
+/// The effective pwquality `minlen` and the file it comes from.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PasswordPolicyEvidence {
+    /// Value as written; `null` when no file sets it
+    pub minlen: Option<String>,
+    pub required: u32,
+    /// The line that sets `minlen` last, which libpwquality uses
+    pub source: Option<Location>,
+}
+
+impl EvidenceView for PasswordPolicyEvidence {
+    fn fields(&self) -> Vec<(&str, String)> {
+        vec![
+            ("minlen", self.minlen.clone().unwrap_or_else(|| "not set".into())),
+            ("required", format!(">= {}", self.required)),
+            ("source", self.source.as_ref().map(Location::to_string).unwrap_or_else(|| "none".into())),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_selects_the_shape_and_untagged_evidence_still_parses() {
+        let evidence = Evidence::SshdSetting(SshdSettingEvidence { setting: "permitrootlogin".into(), value: "no".into(), source: "sshd -T".into(), command: None });
+        let json = serde_json::to_value(&evidence).unwrap();
+        assert_eq!(json["kind"], "sshd_setting");
+        assert!(matches!(serde_json::from_value(json).unwrap(), Evidence::SshdSetting(e) if e.value == "no"));
+
+        // Version 1 reports carried free-form evidence without a `kind`
+        let v1: Evidence = serde_json::from_str(r#"{"ports": 3, "files": "/etc/ssh/sshd_config"}"#).unwrap();
+        let Evidence::Other(value) = &v1 else { panic!("parsed as {:?}", v1) };
+        assert_eq!(value["ports"], 3);
+        assert_eq!(v1.view().fields(), [("files", "/etc/ssh/sshd_config".to_string()), ("ports", "3".to_string())]);
+        assert!(v1.view().table().is_none());
+    }
+}
@@ -0,0 +1,361 @@
+use crate::exec;
+use crate::model::{AuditReport, Issue, Status};
+use crate::redact::Redactor;
+use anyhow::{bail, Context};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const DEFAULT_TEMPLATE: &str = "vps-audit: {host} scored {score} ({score_delta})\n{new_failure_count} new failing checks:\n{new_failures}";
+const DEFAULT_SUBJECT: &str = "vps-audit: {new_failure_count} new failing checks on {host}";
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `--notify <file>`: JSON list of sinks plus where to keep the previous-run state.
+#[derive(Debug, Deserialize)]
+pub struct NotifyConfig {
+    /// Defaults to `$XDG_STATE_HOME/vps-audit/notify-state.json`
+    #[serde(default)]
+    pub state: Option<PathBuf>,
+    pub sinks: Vec<SinkConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SinkConfig {
+    /// Key for the sink's state; defaults to `<type>-<index>`
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub kind: SinkKind,
+    /// Send at most once per interval (`30m`, `1h`); changes in between are batched into the next message
+    #[serde(default, deserialize_with = "duration")]
+    pub min_interval: Option<Duration>,
+    /// Only notify when checks start failing that were not failing at the last notification (default);
+    /// `false` notifies on every run with any WARN or FAIL
+    #[serde(default = "enabled")]
+    pub only_on_change: bool,
+    /// Message body; placeholders: {host} {score} {previous_score} {score_delta} {fail} {warn}
+    /// {new_failures} {new_failure_count} {resolved} {generated_at}
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkKind {
+    /// POSTs a JSON event (see `Event`) with the rendered message in `message`
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    Smtp {
+        server: String,
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        tls: SmtpTls,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+        from: String,
+        to: Vec<String>,
+        #[serde(default)]
+        subject: Option<String>,
+    },
+    /// Runs the program with the message on stdin and the event in `VPS_AUDIT_EVENT`
+    Command { command: Vec<String> },
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    None,
+    #[default]
+    Starttls,
+    Tls,
+}
+
+fn enabled() -> bool { true }
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    value.map(|v| humantime::parse_duration(&v).map_err(serde::de::Error::custom)).transpose()
+}
+
+/// What each sink was last told, so "new" failures are relative to the previous notification.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct State {
+    sinks: BTreeMap<String, SinkState>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+struct SinkState {
+    last_sent: Option<String>,
+    score: Option<u32>,
+    /// Issue key (`check.id` or `check.id:subject`) -> WARN/FAIL
+    failing: BTreeMap<String, Status>,
+}
+
+/// Payload of a notification, also sent as JSON to webhooks and commands.
+#[derive(Debug, Serialize)]
+pub struct Event<'a> {
+    pub host: String,
+    pub generated_at: &'a str,
+    pub score: u32,
+    pub previous_score: Option<u32>,
+    pub score_delta: Option<i64>,
+    pub fail: usize,
+    pub warn: usize,
+    pub new_failures: Vec<NewFailure>,
+    pub resolved: Vec<String>,
+    pub message: String,
+}
+
+/// A WARN/FAIL item that was not failing at the last notification. Owned, unlike [`Issue`], so it can be redacted.
+#[derive(Debug, Serialize)]
+pub struct NewFailure {
+    pub id: String,
+    pub subject: Option<String>,
+    pub status: Status,
+    pub reason: String,
+}
+
+impl NewFailure {
+    /// `check.id` or `check.id:subject`, like [`Issue::key`]
+    pub fn key(&self) -> String {
+        match &self.subject {
+            Some(subject) => format!("{}:{}", self.id, subject),
+            None => self.id.clone(),
+        }
+    }
+}
+
+impl From<Issue<'_>> for NewFailure {
+    fn from(issue: Issue<'_>) -> Self {
+        Self { id: issue.id.into(), subject: issue.subject.map(str::to_string), status: issue.status, reason: issue.reason.into() }
+    }
+}
+
+impl std::fmt::Display for NewFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.status.as_str(), self.key(), self.reason)
+    }
+}
+
+pub struct Notifier {
+    sinks: Vec<(String, SinkConfig)>,
+    state_path: PathBuf,
+}
+
+impl Notifier {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("failed to read notification config {}", path.display()))?;
+        let config: NotifyConfig = serde_json::from_str(&content).with_context(|| format!("invalid notification config {}", path.display()))?;
+        let mut sinks = Vec::new();
+        for (idx, sink) in config.sinks.into_iter().enumerate() {
+            let kind = match &sink.kind { SinkKind::Webhook { .. } => "webhook", SinkKind::Smtp { .. } => "smtp", SinkKind::Command { .. } => "command" };
+            if let SinkKind::Command { command } = &sink.kind {
+                if command.is_empty() { bail!("sink {} has an empty command", idx); }
+            }
+            sinks.push((sink.name.clone().unwrap_or_else(|| format!("{}-{}", kind, idx)), sink));
+        }
+        Ok(Self { sinks, state_path: config.state.unwrap_or_else(default_state_path) })
+    }
+
+    /// Compares `report` with what every sink last saw and sends where due. The state is saved even when a sink
+    /// fails; a failed sink keeps its previous state so the same changes are retried on the next run.
+    /// `report` must be unfiltered and unredacted so the state tracks real issues; with `redactor` only the
+    /// outgoing messages are redacted.
+    pub fn notify(&self, report: &AuditReport, mut redactor: Option<&mut Redactor>) -> anyhow::Result<()> {
+        let mut state: State = std::fs::read_to_string(&self.state_path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default();
+        let failing: BTreeMap<String, Status> = report.results.iter().flat_map(|r| r.issues()).map(|i| (i.key(), i.status)).collect();
+        let now = SystemTime::now();
+        let mut errors = Vec::new();
+
+        for (name, sink) in &self.sinks {
+            let previous = state.sinks.entry(name.clone()).or_default();
+            let last_sent = previous.last_sent.as_deref().and_then(|t| humantime::parse_rfc3339(t).ok());
+            if let (Some(interval), Some(last)) = (sink.min_interval, last_sent) {
+                if now.duration_since(last).unwrap_or_default() < interval { continue; }
+            }
+            let mut event = Event::new(report, previous);
+            if let Some(redactor) = redactor.as_deref_mut() { event.redact(redactor); }
+            event.message = event.render(sink.template.as_deref().unwrap_or(DEFAULT_TEMPLATE));
+            let due = if sink.only_on_change { !event.new_failures.is_empty() } else { !failing.is_empty() };
+            if due {
+                if let Err(e) = sink.kind.send(&event) {
+                    errors.push(format!("{}: {:#}", name, e));
+                    continue;
+                }
+                previous.last_sent = Some(humantime::format_rfc3339_seconds(now).to_string());
+            }
+            previous.score = Some(report.summary.score);
+            previous.failing = failing.clone();
+        }
+
+        if let Some(dir) = self.state_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        crate::output::write_atomic(&self.state_path, serde_json::to_string_pretty(&state)?.as_bytes())
+            .with_context(|| format!("failed to write notification state {}", self.state_path.display()))?;
+        if !errors.is_empty() { bail!("notification failed for {}", errors.join("; ")); }
+        Ok(())
+    }
+}
+
+impl<'a> Event<'a> {
+    /// Event without `message`, which is rendered once the event is redacted.
+    fn new(report: &'a AuditReport, previous: &SinkState) -> Self {
+        let issues: Vec<Issue> = report.results.iter().flat_map(|r| r.issues()).collect();
+        let resolved = previous.failing.keys().filter(|key| !issues.iter().any(|i| &i.key() == *key)).cloned().collect();
+        let new_failures = issues
+            .into_iter()
+            .filter(|i| match previous.failing.get(&i.key()) {
+                None => true,
+                Some(status) => status.is_warn() && i.status.is_fail(),
+            })
+            .map(NewFailure::from)
+            .collect();
+        Self {
+            host: report.host.hostname.clone().unwrap_or_else(|| "localhost".into()),
+            generated_at: &report.generated_at,
+            score: report.summary.score,
+            previous_score: previous.score,
+            score_delta: previous.score.map(|p| report.summary.score as i64 - p as i64),
+            fail: report.summary.fail,
+            warn: report.summary.warn,
+            new_failures,
+            resolved,
+            message: String::new(),
+        }
+    }
+
+    fn redact(&mut self, redactor: &mut Redactor) {
+        self.host = redactor.redact_hostname(&self.host);
+        for failure in &mut self.new_failures {
+            failure.subject = failure.subject.as_deref().map(|s| redactor.redact_str(s));
+            failure.reason = redactor.redact_str(&failure.reason);
+        }
+        for key in &mut self.resolved { *key = redactor.redact_str(key); }
+    }
+
+    fn render(&self, template: &str) -> String {
+        let list = |items: Vec<String>| if items.is_empty() { "- none".to_string() } else { items.join("\n") };
+        let new_failures = list(self.new_failures.iter().map(|i| format!("- {}", i)).collect());
+        let resolved = list(self.resolved.iter().map(|id| format!("- {}", id)).collect());
+        template
+            .replace("{host}", &self.host)
+            .replace("{score}", &self.score.to_string())
+            .replace("{previous_score}", &self.previous_score.map(|s| s.to_string()).unwrap_or_else(|| "n/a".into()))
+            .replace("{score_delta}", &self.score_delta.map(|d| format!("{:+}", d)).unwrap_or_else(|| "first run".into()))
+            .replace("{fail}", &self.fail.to_string())
+            .replace("{warn}", &self.warn.to_string())
+            .replace("{new_failure_count}", &self.new_failures.len().to_string())
+            .replace("{new_failures}", &new_failures)
+            .replace("{resolved}", &resolved)
+            .replace("{generated_at}", self.generated_at)
+    }
+}
+
+impl SinkKind {
+    fn send(&self, event: &Event) -> anyhow::Result<()> {
+        match self {
+            SinkKind::Webhook { url, headers } => {
+                let mut request = ureq::post(url).timeout(SEND_TIMEOUT).set("Content-Type", "application/json");
+                for (name, value) in headers { request = request.set(name, value); }
+                request.send_string(&serde_json::to_string(event)?)?;
+                Ok(())
+            }
+            SinkKind::Smtp { server, port, tls, username, password, from, to, subject } => {
+                use lettre::message::header::ContentType;
+                use lettre::transport::smtp::authentication::Credentials;
+                use lettre::{Message, SmtpTransport, Transport};
+
+                let mut message = Message::builder()
+                    .from(from.parse().with_context(|| format!("invalid from address '{}'", from))?)
+                    .subject(event.render(subject.as_deref().unwrap_or(DEFAULT_SUBJECT)));
+                for recipient in to {
+                    message = message.to(recipient.parse().with_context(|| format!("invalid recipient '{}'", recipient))?);
+                }
+                let message = message.header(ContentType::TEXT_PLAIN).body(event.message.clone())?;
+                let mut transport = match tls {
+                    SmtpTls::None => SmtpTransport::builder_dangerous(server),
+                    SmtpTls::Starttls => SmtpTransport::starttls_relay(server)?,
+                    SmtpTls::Tls => SmtpTransport::relay(server)?,
+                };
+                if let Some(port) = port { transport = transport.port(*port); }
+                if let Some(username) = username {
+                    transport = transport.credentials(Credentials::new(username.clone(), password.clone().unwrap_or_default()));
+                }
+                transport.timeout(Some(SEND_TIMEOUT)).build().send(&message).with_context(|| format!("SMTP delivery via {} failed", server))?;
+                Ok(())
+            }
+            SinkKind::Command { command } => run_command(command, event),
+        }
+    }
+}
+
+fn run_command(command: &[String], event: &Event) -> anyhow::Result<()> {
+    let ids: Vec<String> = event.new_failures.iter().map(|i| i.key()).collect();
+    let (score, delta, ids, json) = (event.score.to_string(), event.score_delta.map(|d| d.to_string()).unwrap_or_default(), ids.join(","), serde_json::to_string(event)?);
+    let env = [("VPS_AUDIT_HOST", event.host.as_str()), ("VPS_AUDIT_SCORE", &score), ("VPS_AUDIT_SCORE_DELTA", &delta), ("VPS_AUDIT_NEW_FAILURES", &ids), ("VPS_AUDIT_EVENT", &json)];
+    let args: Vec<&str> = command[1..].iter().map(String::as_str).collect();
+    let output = exec::run_configured(&command[0], &args, &env, event.message.as_bytes(), SEND_TIMEOUT)?;
+    if !output.success() { bail!("{} failed: {}", command[0], output.error_message()); }
+    Ok(())
+}
+
+fn default_state_path() -> PathBuf {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/state")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("vps-audit").join("notify-state.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{sample_report, Finding};
+
+    fn report_with_finding() -> AuditReport {
+        let mut report = sample_report();
+        report.results[0].findings.push(Finding::new("/home/alice/bin/tool", Status::Fail, "SUID file in /home/alice"));
+        report
+    }
+
+    #[test]
+    fn redaction_only_applies_to_the_outgoing_event() {
+        let report = report_with_finding();
+        let mut event = Event::new(&report, &SinkState::default());
+        event.redact(&mut Redactor::new(&[]));
+        event.message = event.render(DEFAULT_TEMPLATE);
+        assert!(!event.message.contains("alice") && !event.message.contains("web-1"));
+        assert!(event.new_failures.iter().any(|f| f.key() == "ssh.root_login:/home/user-1/bin/tool"));
+        // The report itself keeps the real names the state is keyed on
+        assert_eq!(report.results[0].issues()[0].key(), "ssh.root_login:/home/alice/bin/tool");
+    }
+
+    #[test]
+    fn state_keeps_real_issue_keys_and_only_reports_new_ones() {
+        let state_path = std::env::temp_dir().join(format!("vps-audit-notify-{}.json", std::process::id()));
+        let notifier = Notifier {
+            sinks: vec![("command-0".into(), serde_json::from_str(r#"{"type": "command", "command": ["true"]}"#).unwrap())],
+            state_path: state_path.clone(),
+        };
+        let report = report_with_finding();
+        notifier.notify(&report, Some(&mut Redactor::new(&[]))).unwrap();
+        let state: State = serde_json::from_str(&std::fs::read_to_string(&state_path).unwrap()).unwrap();
+        let failing: Vec<&str> = state.sinks["command-0"].failing.keys().map(String::as_str).collect();
+        assert_eq!(failing, ["ssh.root_login:/home/alice/bin/tool", "system.disk_usage"]);
+
+        let mut previous = state.sinks["command-0"].clone();
+        previous.failing.remove("system.disk_usage");
+        let event = Event::new(&report, &previous);
+        assert_eq!(event.new_failures.iter().map(NewFailure::key).collect::<Vec<_>>(), ["system.disk_usage"]);
+        assert!(event.resolved.is_empty());
+        let _ = std::fs::remove_file(state_path);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::model::AuditReport;
+use crate::report::{OutputFormat, Reporter};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A report file requested with `--output <path>[:format]`.
+#[derive(Clone, Debug)]
+pub struct OutputTarget {
+    pub path: PathBuf,
+    pub format: OutputFormat,
+}
+
+impl std::str::FromStr for OutputTarget {
+    type Err = String;
+
+    /// `report.json`, `report.html`, `audit.out:sarif`, ... An explicit `:format` suffix wins over the extension.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        if let Some((path, format)) = spec.rsplit_once(':') {
+            if let Some(format) = OutputFormat::from_name(format) {
+                if path.is_empty() { return Err(format!("missing path in '{}'", spec)); }
+                return Ok(Self { path: PathBuf::from(path), format });
+            }
+        }
+        let path = PathBuf::from(spec);
+        let format = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(OutputFormat::from_name)
+            .ok_or_else(|| format!("cannot infer report format from '{}'; use <path>:<format> (text, json, ndjson, prometheus, openmetrics, html, sarif, markdown, junit, locations)", spec))?;
+        Ok(Self { path, format })
+    }
+}
+
+impl OutputTarget {
+    pub fn write(&self, reporter: &Reporter, report: &AuditReport) -> io::Result<()> {
+        let mut contents = reporter.plain().with_format(self.format).render(report);
+        if !contents.ends_with('\n') { contents.push('\n'); }
+        write_atomic(&self.path, contents.as_bytes())
+    }
+}
+
+/// Directory `path` is written in, `.` for a bare file name.
+pub fn output_dir(path: &Path) -> &Path {
+    match path.parent() { Some(p) if !p.as_os_str().is_empty() => p, _ => Path::new(".") }
+}
+
+/// Writes `contents` to a temporary file next to `path` with mode 0600 and renames it into place,
+/// so readers never see a partial report and evidence is not world readable.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = output_dir(path);
+    let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "output path has no file name"))?;
+    let tmp = dir.join(format!(".{}.{}.tmp", name.to_string_lossy(), std::process::id()));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let result = options.open(&tmp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = result.and_then(|_| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    // Persist the rename itself; best-effort since not every filesystem supports syncing directories
+    if let Ok(d) = fs::File::open(dir) { let _ = d.sync_all(); }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(spec: &str) -> Result<(PathBuf, OutputFormat), String> {
+        spec.parse::<OutputTarget>().map(|t| (t.path, t.format))
+    }
+
+    #[test]
+    fn format_comes_from_suffix_or_extension() {
+        assert_eq!(parse("report.html"), Ok(("report.html".into(), OutputFormat::Html)));
+        assert_eq!(parse("out/REPORT.MD"), Ok(("out/REPORT.MD".into(), OutputFormat::Markdown)));
+        assert_eq!(parse("audit.out:sarif"), Ok(("audit.out".into(), OutputFormat::Sarif)));
+        // The suffix wins over the extension
+        assert_eq!(parse("report.json:junit"), Ok(("report.json".into(), OutputFormat::Junit)));
+        // A colon that does not name a format is part of the path
+        assert_eq!(parse("runs/12:30.json"), Ok(("runs/12:30.json".into(), OutputFormat::Json)));
+    }
+
+    #[test]
+    fn unknown_format_and_missing_path_are_rejected() {
+        assert!(parse("report.pdf").unwrap_err().contains("cannot infer report format"));
+        assert!(parse("report").is_err());
+        assert_eq!(parse(":json").unwrap_err(), "missing path in ':json'");
+    }
+}
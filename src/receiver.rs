@@ -0,0 +1,448 @@
+use crate::model::{AuditReport, Status};
+use crate::report::escape_xml as esc;
+use anyhow::{anyhow, Context};
+use ed25519_dalek::VerifyingKey;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Read;
+use std::path::Path;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Largest accepted upload; real reports are a few tens of kilobytes.
+const MAX_UPLOAD_BYTES: u64 = 8 * 1024 * 1024;
+/// Header carrying the agent id with `--push`; history is kept per agent, not per reported hostname.
+pub const AGENT_HEADER: &str = "X-Vps-Audit-Agent";
+
+pub struct ReceiverOptions {
+    pub listen: String,
+    /// Bearer token agents must send with `--push`
+    pub token: String,
+    /// Only accept reports signed with this key
+    pub pubkey: Option<VerifyingKey>,
+}
+
+/// Per-host report history in SQLite.
+pub struct ReportStore {
+    conn: Connection,
+}
+
+/// Latest state of a host, compared with its previous upload.
+#[derive(Debug, Serialize)]
+pub struct HostStatus {
+    /// Agent id the history is keyed on
+    pub agent: String,
+    /// Hostname from the latest report; a pseudonym such as `host-1` for redacted reports
+    pub host: String,
+    pub received_at: String,
+    pub generated_at: String,
+    pub score: u32,
+    pub fail: usize,
+    pub warn: usize,
+    pub reports: usize,
+    pub previous_score: Option<u32>,
+    /// Checks that were PASS or SKIP in the previous report and WARN or FAIL now
+    pub regressions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub received_at: String,
+    pub generated_at: String,
+    pub score: u32,
+    pub fail: usize,
+    pub warn: usize,
+}
+
+impl ReportStore {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = Connection::open(path).with_context(|| format!("failed to open database {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS reports (
+                id INTEGER PRIMARY KEY,
+                host TEXT NOT NULL,
+                received_at TEXT NOT NULL,
+                generated_at TEXT NOT NULL,
+                score INTEGER NOT NULL,
+                fail INTEGER NOT NULL,
+                warn INTEGER NOT NULL,
+                report TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS reports_host ON reports (host, id);
+            CREATE TABLE IF NOT EXISTS agents (
+                agent TEXT PRIMARY KEY,
+                public_key TEXT
+            );",
+        )?;
+        // Databases from before agent ids keyed history on the hostname, which becomes the agent id of those rows
+        let has_agent: bool = conn.query_row("SELECT COUNT(*) FROM pragma_table_info('reports') WHERE name = 'agent'", [], |row| row.get(0))?;
+        if !has_agent {
+            conn.execute_batch(
+                "ALTER TABLE reports ADD COLUMN agent TEXT;
+                UPDATE reports SET agent = host;",
+            )?;
+        }
+        conn.execute_batch("CREATE INDEX IF NOT EXISTS reports_agent ON reports (agent, id);")?;
+        Ok(Self { conn })
+    }
+
+    /// Binds `agent` to the key its reports are signed with on first use. Returns false when the agent is already
+    /// bound to another key, or was signed before and `public_key` is `None`, so one agent cannot write another's history.
+    pub fn bind_agent(&self, agent: &str, public_key: Option<&str>) -> anyhow::Result<bool> {
+        let bound: Option<Option<String>> =
+            self.conn.query_row("SELECT public_key FROM agents WHERE agent = ?1", [agent], |row| row.get(0)).optional()?;
+        match bound {
+            Some(Some(key)) => Ok(public_key == Some(key.as_str())),
+            Some(None) => {
+                self.conn.execute("UPDATE agents SET public_key = ?2 WHERE agent = ?1", params![agent, public_key])?;
+                Ok(true)
+            }
+            None => {
+                self.conn.execute("INSERT INTO agents (agent, public_key) VALUES (?1, ?2)", params![agent, public_key])?;
+                Ok(true)
+            }
+        }
+    }
+
+    pub fn insert(&self, agent: &str, report: &AuditReport, json: &str) -> anyhow::Result<()> {
+        self.conn.execute(
+            "INSERT INTO reports (agent, host, received_at, generated_at, score, fail, warn, report) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                agent,
+                host_name(report),
+                humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+                report.generated_at,
+                report.summary.score,
+                report.summary.fail,
+                report.summary.warn,
+                json
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn hosts(&self) -> anyhow::Result<Vec<HostStatus>> {
+        let mut stmt = self.conn.prepare("SELECT agent, COUNT(*) FROM reports GROUP BY agent ORDER BY agent")?;
+        let agents: Vec<(String, usize)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<_, _>>()?;
+        let mut statuses = Vec::new();
+        for (agent, count) in agents {
+            let mut recent = self.recent(&agent, 2)?;
+            let Some((received_at, latest)) = (!recent.is_empty()).then(|| recent.remove(0)) else { continue };
+            let previous = recent.pop().map(|(_, r)| r);
+            statuses.push(HostStatus {
+                regressions: previous.as_ref().map(|p| regressions(p, &latest)).unwrap_or_default(),
+                previous_score: previous.map(|p| p.summary.score),
+                host: host_name(&latest),
+                agent,
+                received_at,
+                generated_at: latest.generated_at,
+                score: latest.summary.score,
+                fail: latest.summary.fail,
+                warn: latest.summary.warn,
+                reports: count,
+            });
+        }
+        // Worst hosts first, like `vps-audit aggregate`
+        statuses.sort_by(|a, b| a.score.cmp(&b.score).then(a.host.cmp(&b.host)).then(a.agent.cmp(&b.agent)));
+        Ok(statuses)
+    }
+
+    pub fn history(&self, agent: &str) -> anyhow::Result<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare("SELECT received_at, generated_at, score, fail, warn FROM reports WHERE agent = ?1 ORDER BY id DESC")?;
+        let rows = stmt.query_map([agent], |row| {
+            Ok(HistoryEntry { received_at: row.get(0)?, generated_at: row.get(1)?, score: row.get(2)?, fail: row.get(3)?, warn: row.get(4)? })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn latest(&self, agent: &str) -> anyhow::Result<Option<String>> {
+        let json = self
+            .conn
+            .query_row("SELECT report FROM reports WHERE agent = ?1 ORDER BY id DESC LIMIT 1", [agent], |row| row.get(0))
+            .optional()?;
+        Ok(json)
+    }
+
+    fn recent(&self, agent: &str, limit: usize) -> anyhow::Result<Vec<(String, AuditReport)>> {
+        let mut stmt = self.conn.prepare("SELECT received_at, report FROM reports WHERE agent = ?1 ORDER BY id DESC LIMIT ?2")?;
+        let rows: Vec<(String, String)> = stmt.query_map(params![agent, limit], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<_, _>>()?;
+        rows.into_iter().map(|(at, json)| Ok((at, serde_json::from_str(&json)?))).collect()
+    }
+}
+
+fn host_name(report: &AuditReport) -> String {
+    report.host.hostname.clone().unwrap_or_else(|| "unknown".into())
+}
+
+/// Agent ids end up in URLs and SQL keys; keep them to a short, path-safe alphabet.
+fn valid_agent_id(agent: &str) -> bool {
+    (1..=64).contains(&agent.len()) && agent.bytes().all(|b| b.is_ascii_alphanumeric() || b"._-".contains(&b))
+}
+
+/// Default `--agent-id`: a hash of /etc/machine-id, stable across runs and redaction without exposing the id itself.
+pub fn default_agent_id() -> Option<String> {
+    let machine_id = crate::trace::read_to_string("/etc/machine-id").ok()?;
+    let machine_id = machine_id.trim();
+    if machine_id.is_empty() { return None; }
+    let digest = Sha256::digest(format!("vps-audit:{}", machine_id));
+    Some(digest.iter().take(8).map(|b| format!("{:02x}", b)).collect())
+}
+
+/// WARN/FAIL issues of checks that also ran last time, where the same item was passing or only warned before.
+fn regressions(previous: &AuditReport, latest: &AuditReport) -> Vec<String> {
+    let before: BTreeMap<String, Status> = previous.results.iter().flat_map(|r| r.issues()).map(|i| (i.key(), i.status)).collect();
+    latest
+        .results
+        .iter()
+        .filter(|r| previous.results.iter().any(|p| p.id == r.id))
+        .flat_map(|r| r.issues())
+        .filter(|i| match before.get(&i.key()) {
+            None => true,
+            Some(status) => status.is_warn() && i.status.is_fail(),
+        })
+        .map(|i| format!("{} ({})", i.key(), i.status.as_str()))
+        .collect()
+}
+
+/// Accepts `POST /api/reports` from agents and serves a read-only overview of the stored history.
+pub struct ReportReceiver {
+    store: ReportStore,
+    options: ReceiverOptions,
+}
+
+impl ReportReceiver {
+    pub fn new(store: ReportStore, options: ReceiverOptions) -> Self {
+        Self { store, options }
+    }
+
+    pub fn run(&self) -> anyhow::Result<()> {
+        let server = Server::http(&self.options.listen).map_err(|e| anyhow!("failed to listen on {}: {}", self.options.listen, e))?;
+        eprintln!("vps-audit collector on http://{} (POST /api/reports, /, /api/hosts, /healthz)", self.options.listen);
+        for request in server.incoming_requests() {
+            self.handle(request);
+        }
+        Ok(())
+    }
+
+    fn handle(&self, mut request: Request) {
+        let path = request.url().split('?').next().unwrap_or("/").to_string();
+        let response = match (request.method(), path.as_str()) {
+            (Method::Post, "/api/reports") => self.upload(&mut request),
+            (Method::Get | Method::Head, _) => self.page(&path),
+            (_, "/api/reports") => text("method not allowed\n", 405).with_header(header("Allow", "POST")),
+            _ => text("method not allowed\n", 405).with_header(header("Allow", "GET, HEAD")),
+        };
+        let _ = request.respond(response);
+    }
+
+    fn upload(&self, request: &mut Request) -> Response<std::io::Cursor<Vec<u8>>> {
+        let expected = format!("Bearer {}", self.options.token);
+        let authorized = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Authorization"))
+            .map(|h| constant_time_eq(h.value.as_str().as_bytes(), expected.as_bytes()))
+            .unwrap_or(false);
+        if !authorized {
+            return text("unauthorized\n", 401).with_header(header("WWW-Authenticate", "Bearer"));
+        }
+        let mut body = String::new();
+        if let Err(e) = request.as_reader().take(MAX_UPLOAD_BYTES + 1).read_to_string(&mut body) {
+            return text(&format!("invalid body: {}\n", e), 400);
+        }
+        if body.len() as u64 > MAX_UPLOAD_BYTES {
+            return text("report too large\n", 413);
+        }
+        let agent = request.headers().iter().find(|h| h.field.equiv(AGENT_HEADER)).map(|h| h.value.as_str().trim().to_string());
+        let Some(agent) = agent.filter(|a| valid_agent_id(a)) else {
+            return text(&format!("missing or invalid {} header (1-64 characters of A-Z, a-z, 0-9, '.', '_', '-')\n", AGENT_HEADER), 400);
+        };
+        let report: AuditReport = match serde_json::from_str(&body) {
+            Ok(r) => r,
+            Err(e) => return text(&format!("not a vps-audit JSON report: {}\n", e), 400),
+        };
+        // Without --pubkey a signed report is checked against its own key, which still proves who holds the agent id
+        let key = match (&self.options.pubkey, &report.signature) {
+            (Some(key), _) => Some(*key),
+            (None, Some(signature)) => match crate::sign::decode_verifying_key(&signature.public_key) {
+                Ok(key) => Some(key),
+                Err(e) => return text(&format!("signature rejected: {:#}\n", e), 403),
+            },
+            (None, None) => None,
+        };
+        if let Some(key) = &key {
+            if let Err(e) = crate::sign::verify_report(&body, key) {
+                return text(&format!("signature rejected: {:#}\n", e), 403);
+            }
+        }
+        let signer = report.signature.as_ref().map(|s| s.public_key.as_str());
+        match self.store.bind_agent(&agent, signer) {
+            Ok(true) => {}
+            Ok(false) => return text(&format!("agent {} is bound to a different signing key\n", agent), 403),
+            Err(e) => {
+                eprintln!("error: failed to look up agent: {:#}", e);
+                return text("failed to store report\n", 500);
+            }
+        }
+        match self.store.insert(&agent, &report, &body) {
+            Ok(()) => {
+                eprintln!("stored report from {} ({}, score {})", agent, host_name(&report), report.summary.score);
+                text("stored\n", 201)
+            }
+            Err(e) => {
+                eprintln!("error: failed to store report: {:#}", e);
+                text("failed to store report\n", 500)
+            }
+        }
+    }
+
+    fn page(&self, path: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+        let result = match path {
+            "/" => self.store.hosts().map(|hosts| html(render_hosts(&hosts))),
+            "/api/hosts" => self.store.hosts().map(|hosts| json(serde_json::to_string_pretty(&hosts).unwrap())),
+            "/healthz" => return text("ok\n", 200),
+            _ => {
+                if let Some(agent) = path.strip_prefix("/hosts/") {
+                    self.store.history(agent).map(|h| if h.is_empty() { text("not found\n", 404) } else { html(render_history(agent, &h)) })
+                } else if let Some(agent) = path.strip_prefix("/api/hosts/").and_then(|p| p.strip_suffix("/latest")) {
+                    self.store.latest(agent).map(|r| r.map(json).unwrap_or_else(|| text("not found\n", 404)))
+                } else {
+                    return text("not found\n", 404);
+                }
+            }
+        };
+        result.unwrap_or_else(|e| {
+            eprintln!("error: {:#}", e);
+            text("internal error\n", 500)
+        })
+    }
+}
+
+fn render_hosts(hosts: &[HostStatus]) -> String {
+    let mut s = page_head("VPS audit collector");
+    let _ = writeln!(s, "<h1>VPS audit collector</h1>\n<p class=\"muted\">{} hosts</p>", hosts.len());
+    let _ = writeln!(s, "<table>\n<tr><th>Host</th><th>Agent</th><th>Score</th><th>Change</th><th>FAIL</th><th>WARN</th><th>Last report</th><th>Regressions</th></tr>");
+    for h in hosts {
+        let change = match h.previous_score {
+            Some(prev) => format!("{:+}", h.score as i64 - prev as i64),
+            None => "new".into(),
+        };
+        let _ = writeln!(
+            s,
+            "<tr><td><a href=\"/hosts/{0}\">{1}</a></td><td class=\"muted\">{0}</td><td>{2}</td><td>{3}</td><td>{4}</td><td>{5}</td><td class=\"muted\">{6}</td><td>{7}</td></tr>",
+            esc(&h.agent), esc(&h.host), h.score, change, h.fail, h.warn, esc(&h.received_at), esc(&h.regressions.join(", "))
+        );
+    }
+    let _ = writeln!(s, "</table>\n</body>\n</html>");
+    s
+}
+
+fn render_history(agent: &str, history: &[HistoryEntry]) -> String {
+    let mut s = page_head(&format!("VPS audit: {}", agent));
+    let _ = writeln!(s, "<p><a href=\"/\">&larr; all hosts</a></p>\n<h1>{}</h1>", esc(agent));
+    let _ = writeln!(s, "<p><a href=\"/api/hosts/{}/latest\">latest report (JSON)</a></p>", esc(agent));
+    let _ = writeln!(s, "<table>\n<tr><th>Received</th><th>Generated</th><th>Score</th><th>FAIL</th><th>WARN</th></tr>");
+    for e in history {
+        let _ = writeln!(
+            s,
+            "<tr><td>{}</td><td class=\"muted\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            esc(&e.received_at), esc(&e.generated_at), e.score, e.fail, e.warn
+        );
+    }
+    let _ = writeln!(s, "</table>\n</body>\n</html>");
+    s
+}
+
+fn page_head(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        esc(title),
+        crate::report::html::STYLE
+    )
+}
+
+/// Uploads a rendered JSON report to a collector started with `vps-audit serve-collector`.
+pub fn push(url: &str, token: &str, agent: &str, json: &str) -> anyhow::Result<()> {
+    let endpoint = format!("{}/api/reports", url.trim_end_matches('/'));
+    ureq::post(&endpoint)
+        .timeout(std::time::Duration::from_secs(30))
+        .set("Authorization", &format!("Bearer {}", token))
+        .set(AGENT_HEADER, agent)
+        .set("Content-Type", "application/json")
+        .send_string(json)
+        .map_err(|e| match e {
+            ureq::Error::Status(code, response) => {
+                anyhow!("{} answered {}: {}", endpoint, code, response.into_string().unwrap_or_default().trim())
+            }
+            other => anyhow!("failed to reach {}: {}", endpoint, other),
+        })?;
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn text(body: &str, status: u16) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body).with_status_code(status).with_header(header("Content-Type", "text/plain; charset=utf-8"))
+}
+
+fn html(body: String) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body).with_header(header("Content-Type", "text/html; charset=utf-8"))
+}
+
+fn json(body: String) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body).with_header(header("Content-Type", "application/json"))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("static header is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_report;
+
+    fn store() -> ReportStore {
+        ReportStore::open(Path::new(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn redacted_hosts_are_kept_apart_by_agent() {
+        let store = store();
+        let mut report = sample_report();
+        report.host.hostname = Some("host-1".into());
+        let json = serde_json::to_string(&report).unwrap();
+        store.insert("agent-a", &report, &json).unwrap();
+        store.insert("agent-b", &report, &json).unwrap();
+        let hosts = store.hosts().unwrap();
+        assert_eq!(hosts.iter().map(|h| h.agent.as_str()).collect::<Vec<_>>(), ["agent-a", "agent-b"]);
+        assert!(hosts.iter().all(|h| h.host == "host-1" && h.reports == 1));
+    }
+
+    #[test]
+    fn agent_is_bound_to_its_first_signing_key() {
+        let store = store();
+        assert!(store.bind_agent("web-1", Some("key-a")).unwrap());
+        assert!(store.bind_agent("web-1", Some("key-a")).unwrap());
+        assert!(!store.bind_agent("web-1", Some("key-b")).unwrap());
+        assert!(!store.bind_agent("web-1", None).unwrap());
+        // Unsigned agents get bound once they start signing
+        assert!(store.bind_agent("web-2", None).unwrap());
+        assert!(store.bind_agent("web-2", Some("key-b")).unwrap());
+        assert!(!store.bind_agent("web-2", Some("key-c")).unwrap());
+    }
+
+    #[test]
+    fn agent_ids_are_path_safe() {
+        assert!(valid_agent_id("3f2a9c1e0b7d4a65"));
+        assert!(valid_agent_id("web-1.example_com"));
+        assert!(!valid_agent_id(""));
+        assert!(!valid_agent_id("../etc"));
+        assert!(!valid_agent_id("a b"));
+        assert!(!valid_agent_id(&"a".repeat(65)));
+    }
+}
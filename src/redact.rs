@@ -0,0 +1,217 @@
+use crate::collectors::SystemInfo;
+use crate::model::{AuditReport, CheckResult, Evidence};
+use regex::Regex;
+use std::collections::HashMap;
+
+struct Rule {
+    /// Pseudonym prefix, e.g. `ip` for `ip-1`
+    label: String,
+    regex: Regex,
+}
+
+/// Replaces sensitive values with stable pseudonyms (`ip-1`, `host-1`, `user-1`, ...).
+/// The same original value always maps to the same pseudonym for the lifetime of the redactor,
+/// so a redacted report still reads coherently across results and output formats.
+/// When a pattern has a capture group, only the first group is replaced (`/home/alice` -> `/home/user-1`).
+pub struct Redactor {
+    rules: Vec<Rule>,
+    pseudonyms: HashMap<(String, String), String>,
+    counters: HashMap<String, usize>,
+}
+
+impl Redactor {
+    /// Built-in patterns (IP addresses, hostnames, local usernames, home paths) plus `custom` patterns.
+    pub fn new(custom: &[Regex]) -> Self {
+        Self::with_identity(custom, sysinfo::System::host_name(), &local_users())
+    }
+
+    /// Like [`Redactor::new`] with the hostname and local users given instead of read from this host.
+    fn with_identity(custom: &[Regex], hostname: Option<String>, users: &[String]) -> Self {
+        let mut rules = Vec::new();
+        let mut push = |label: &str, pattern: &str| {
+            rules.push(Rule { label: label.into(), regex: Regex::new(pattern).expect("built-in redaction pattern is valid") });
+        };
+
+        // Home directories first so the username inside the path becomes the same `user-N` as elsewhere
+        push("user", r"/home/([^/\s'\x22,;:]+)");
+        push("ip", r"\b(?:25[0-5]|2[0-4]\d|1?\d?\d)(?:\.(?:25[0-5]|2[0-4]\d|1?\d?\d)){3}\b");
+        push("ip", r"(?i)\b(?:[0-9a-f]{1,4}:){7}[0-9a-f]{1,4}\b");
+        // Compressed IPv6 (`fe80::1`, `::1`); a bare `::` is left alone
+        push("ip", r"(?i)\b[0-9a-f]{1,4}(?::[0-9a-f]{1,4}){0,6}::(?:[0-9a-f]{1,4}(?::[0-9a-f]{1,4}){0,6}\b)?|::[0-9a-f]{1,4}(?::[0-9a-f]{1,4}){0,6}\b");
+        if let Some(hostname) = hostname.filter(|h| !h.is_empty()) {
+            let short = hostname.split('.').next().unwrap_or(&hostname).to_string();
+            push("host", &format!(r"(?i)\b(?:{}|{})\b", regex::escape(&hostname), regex::escape(&short)));
+        }
+        // Fully qualified names need at least two dots so file names like pwquality.conf are left alone
+        push("host", r"(?i)\b(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.){2,}[a-z]{2,63}\b");
+        if !users.is_empty() {
+            let alternatives: Vec<String> = users.iter().map(|u| regex::escape(u)).collect();
+            push("user", &format!(r"\b(?:{})\b", alternatives.join("|")));
+        }
+
+        for regex in custom {
+            rules.push(Rule { label: "redacted".into(), regex: regex.clone() });
+        }
+        Self { rules, pseudonyms: HashMap::new(), counters: HashMap::new() }
+    }
+
+    /// Redacts every free-text field of the report. Identifiers the tool itself defines (check ids, control ids,
+    /// timestamps, versions) are left alone; control ids such as CIS `1.1.1.1` would otherwise read as addresses.
+    pub fn redact_report(&mut self, report: &mut AuditReport) {
+        self.redact_system(&mut report.host);
+        for r in &mut report.results { self.redact_result(r); }
+        for note in report.privileges.root_would_add.values_mut() { *note = self.redact_str(note); }
+        if let Some(sandbox) = &mut report.sandbox {
+            for path in sandbox.writable.iter_mut().chain(&mut sandbox.executable) { *path = self.redact_str(path); }
+        }
+    }
+
+    pub fn redact_system(&mut self, system: &mut SystemInfo) {
+        if let Some(hostname) = &system.hostname {
+            system.hostname = Some(self.redact_hostname(hostname));
+        }
+        if let Some(os) = &system.os_pretty_name { system.os_pretty_name = Some(self.redact_str(os)); }
+        if let Some(kernel) = &system.kernel_version { system.kernel_version = Some(self.redact_str(kernel)); }
+    }
+
+    /// Pseudonym of the whole hostname, whether or not it matches a hostname pattern.
+    pub fn redact_hostname(&mut self, hostname: &str) -> String {
+        self.pseudonym("host", hostname)
+    }
+
+    pub fn redact_result(&mut self, result: &mut CheckResult) {
+        result.reason = self.redact_str(&result.reason);
+        if let Some(remediation) = &result.remediation { result.remediation = Some(self.redact_str(remediation)); }
+        if let Some(evidence) = &mut result.evidence { self.redact_evidence(evidence); }
+        for location in &mut result.locations {
+            location.path = self.redact_str(&location.path);
+            if let Some(text) = &location.text { location.text = Some(self.redact_str(text)); }
+        }
+        if let Some(note) = &result.needs_root { result.needs_root = Some(self.redact_str(note)); }
+        for finding in &mut result.findings {
+            finding.subject = self.redact_str(&finding.subject);
+            finding.reason = self.redact_str(&finding.reason);
+            if let Some(evidence) = &mut finding.evidence { self.redact_evidence(evidence); }
+        }
+    }
+
+    /// Redacts the strings inside typed evidence, leaving its `kind` alone so it still parses as the same type.
+    fn redact_evidence(&mut self, evidence: &mut Evidence) {
+        let Ok(mut value) = serde_json::to_value(&*evidence) else { return };
+        let kind = value.as_object_mut().and_then(|map| map.remove("kind"));
+        self.redact_value(&mut value);
+        if let (Some(kind), Some(map)) = (kind, value.as_object_mut()) { map.insert("kind".into(), kind); }
+        *evidence = serde_json::from_value(value.clone()).unwrap_or(Evidence::Other(value));
+    }
+
+    fn redact_value(&mut self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(s) => *s = self.redact_str(s),
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|v| self.redact_value(v)),
+            serde_json::Value::Object(map) => map.values_mut().for_each(|v| self.redact_value(v)),
+            _ => {}
+        }
+    }
+
+    pub fn redact_str(&mut self, text: &str) -> String {
+        let mut out = text.to_string();
+        for i in 0..self.rules.len() {
+            let regex = self.rules[i].regex.clone();
+            let label = self.rules[i].label.clone();
+            out = regex
+                .replace_all(&out, |caps: &regex::Captures| {
+                    let whole = caps.get(0).map(|m| m.as_str()).unwrap_or_default();
+                    match caps.get(1) {
+                        Some(group) => {
+                            let start = group.start() - caps.get(0).map(|m| m.start()).unwrap_or(0);
+                            let pseudonym = self.pseudonym(&label, group.as_str());
+                            format!("{}{}{}", &whole[..start], pseudonym, &whole[start + group.as_str().len()..])
+                        }
+                        None => self.pseudonym(&label, whole),
+                    }
+                })
+                .into_owned();
+        }
+        out
+    }
+
+    fn pseudonym(&mut self, label: &str, original: &str) -> String {
+        // Hostnames are case-insensitive; everything else is matched verbatim
+        let key_value = if label == "host" { original.to_ascii_lowercase() } else { original.to_string() };
+        let key = (label.to_string(), key_value);
+        if let Some(existing) = self.pseudonyms.get(&key) { return existing.clone(); }
+        let counter = self.counters.entry(label.to_string()).or_insert(0);
+        *counter += 1;
+        let pseudonym = format!("{}-{}", label, counter);
+        self.pseudonyms.insert(key, pseudonym.clone());
+        pseudonym
+    }
+}
+
+/// Regular login accounts from /etc/passwd (uid >= 1000, excluding nobody); system accounts such as root are not sensitive.
+fn local_users() -> Vec<String> {
+    let content = crate::trace::read_to_string("/etc/passwd").unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid: u32 = fields.nth(1)?.parse().ok()?;
+            (uid >= 1000 && uid != 65534 && !name.is_empty()).then(|| name.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{sample_report, SandboxSummary};
+
+    fn redactor(custom: &[Regex]) -> Redactor {
+        Redactor::with_identity(custom, Some("web-1.example.com".into()), &["alice".into(), "bob".into()])
+    }
+
+    #[test]
+    fn pseudonyms_are_stable_across_calls() {
+        let mut r = redactor(&[]);
+        assert_eq!(r.redact_str("login from 203.0.113.7 and 198.51.100.2"), "login from ip-1 and ip-2");
+        assert_eq!(r.redact_str("again 198.51.100.2, then 203.0.113.7"), "again ip-2, then ip-1");
+        assert_eq!(r.redact_hostname("WEB-1.example.com"), r.redact_str("web-1.example.com"));
+        assert_eq!(r.redact_str("web-1 answers"), "host-2 answers");
+    }
+
+    #[test]
+    fn home_paths_and_users_share_a_pseudonym() {
+        let mut r = redactor(&[]);
+        assert_eq!(r.redact_str("/home/alice/.ssh/authorized_keys"), "/home/user-1/.ssh/authorized_keys");
+        assert_eq!(r.redact_str("owned by alice, not bob"), "owned by user-1, not user-2");
+        assert_eq!(r.redact_str("/home/carol/bin"), "/home/user-3/bin");
+        // File names with a single dot are not hostnames
+        assert_eq!(r.redact_str("/etc/security/pwquality.conf"), "/etc/security/pwquality.conf");
+    }
+
+    #[test]
+    fn custom_pattern_replaces_only_its_group() {
+        let mut r = redactor(&[Regex::new(r"token=(\w+)").unwrap()]);
+        assert_eq!(r.redact_str("url?token=abc123&x=1"), "url?token=redacted-1&x=1");
+    }
+
+    #[test]
+    fn report_envelope_is_redacted() {
+        let mut report = sample_report();
+        report.sandbox = Some(SandboxSummary {
+            landlock_abi: 3,
+            allowed_syscalls: 100,
+            writable: vec!["/home/alice/.cache/vps-audit".into()],
+            executable: vec!["/usr/sbin/sshd".into()],
+        });
+        report.privileges.root_would_add.insert("files.suid".into(), "scans /home/alice".into());
+        report.results[0].needs_root = Some("reads /home/alice/.ssh".into());
+        redactor(&[]).redact_report(&mut report);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(!json.contains("alice") && !json.contains("web-1"), "{}", json);
+        assert_eq!(report.results[2].reason, "Disk usage 81% on /home/user-1");
+        assert_eq!(report.sandbox.unwrap().writable, ["/home/user-1/.cache/vps-audit"]);
+        assert_eq!(report.host.hostname.as_deref(), Some("host-1"));
+    }
+}
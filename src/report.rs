@@ -1,75 +1,226 @@
-use crate::model::{CheckResult, Status};
+use crate::collectors::SystemInfo;
+use crate::model::{AuditReport, CheckResult, HostMetrics, ReportFilters, Status, Summary, ToolInfo};
+use serde::Serialize;
+use std::fmt::Write as _;
 
-#[derive(Clone, Copy)]
-pub enum OutputFormat { Text, Json }
+pub mod html;
+pub mod junit;
+pub mod markdown;
+pub mod sarif;
+pub mod text;
 
+pub use text::TextOptions;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat { Text, Json, Ndjson, Prometheus, OpenMetrics, Html, Sarif, Markdown, Junit, Locations }
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "text" | "txt" => OutputFormat::Text,
+            "json" => OutputFormat::Json,
+            "ndjson" | "jsonl" => OutputFormat::Ndjson,
+            "prometheus" | "prom" => OutputFormat::Prometheus,
+            "openmetrics" => OutputFormat::OpenMetrics,
+            "html" | "htm" => OutputFormat::Html,
+            "sarif" => OutputFormat::Sarif,
+            "markdown" | "md" => OutputFormat::Markdown,
+            "junit" | "xml" => OutputFormat::Junit,
+            "locations" => OutputFormat::Locations,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone)]
 pub struct Reporter {
     verbose: bool,
     format: OutputFormat,
+    text: TextOptions,
 }
 
 impl Reporter {
-    pub fn new(verbose: bool, format: OutputFormat) -> Self { Self { verbose, format } }
+    pub fn new(verbose: bool, format: OutputFormat) -> Self { Self { verbose, format, text: TextOptions::default() } }
 
-    pub fn print(&self, results: &[CheckResult]) {
-        match self.format {
-            OutputFormat::Text => self.print_text(results),
-            OutputFormat::Json => self.print_json(results),
-        }
+    pub fn with_text_options(mut self, text: TextOptions) -> Self {
+        self.text = text;
+        self
     }
 
-    pub fn render(&self, results: &[CheckResult]) -> String {
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Same reporter without terminal styling, for writing reports to files.
+    pub fn plain(&self) -> Self {
+        let mut plain = self.clone();
+        plain.text.color = false;
+        plain.text.width = None;
+        plain
+    }
+
+    pub fn print(&self, report: &AuditReport) {
         match self.format {
-            OutputFormat::Text => self.render_text(results),
-            OutputFormat::Json => self.render_json(results),
+            OutputFormat::Text => self.print_text(report),
+            OutputFormat::Json => self.print_json(report),
+            _ => print!("{}", self.render(report)),
         }
     }
 
-    fn print_text(&self, results: &[CheckResult]) {
-        println!("{}", self.render_text(results));
+    pub fn render(&self, report: &AuditReport) -> String {
+        match self.format {
+            OutputFormat::Text => self.render_text(report),
+            OutputFormat::Json => Self::render_json(report),
+            OutputFormat::Ndjson => {
+                let mut s = String::new();
+                for r in &report.results { let _ = writeln!(s, "{}", Self::ndjson_result(r)); }
+                let _ = writeln!(s, "{}", Self::ndjson_summary(report));
+                s
+            }
+            OutputFormat::Prometheus => Self::render_metrics(report, false),
+            OutputFormat::OpenMetrics => Self::render_metrics(report, true),
+            OutputFormat::Html => html::render(report),
+            OutputFormat::Sarif => sarif::render(report) + "\n",
+            OutputFormat::Markdown => markdown::render(report),
+            OutputFormat::Junit => junit::render(report),
+            OutputFormat::Locations => Self::render_locations(report),
+        }
     }
 
-    fn render_text(&self, results: &[CheckResult]) -> String {
+    /// `path:line: STATUS id: reason` for every WARN/FAIL with a known location, like compiler diagnostics,
+    /// so editors can jump straight to the line (e.g. vim's quickfix list or VS Code's terminal links).
+    fn render_locations(report: &AuditReport) -> String {
         let mut s = String::new();
-        s.push_str("VPS Audit Results\n");
-        s.push_str("=================\n");
-        let (pass, warn, fail, skip) = Self::counts(results);
-        let score = Self::score(results);
-        s.push_str(&format!("Score: {} / 100\n", score));
-        s.push_str(&format!("PASS={}, WARN={}, FAIL={}, SKIP={}\n", pass, warn, fail, skip));
-        for r in results {
-            if !self.verbose && matches!(r.status, Status::Pass | Status::Skip) { continue; }
-            s.push_str(&format!("[{}] {}\n", match r.status { Status::Pass => "PASS", Status::Warn => "WARN", Status::Fail => "FAIL", Status::Skip => "SKIP" }, r.title));
-            s.push_str(&format!("  id: {}\n", r.id));
-            if !r.categories.is_empty() { s.push_str(&format!("  categories: {}\n", r.categories.join(", "))); }
-            s.push_str(&format!("  reason: {}\n", r.reason));
-            if let Some(remediation) = &r.remediation { s.push_str(&format!("  remediation: {}\n", remediation)); }
-            if let Some(evidence) = &r.evidence { s.push_str(&format!("  evidence: {}\n", evidence)); }
-            s.push('\n');
+        for r in report.results.iter().filter(|r| r.status.is_fail() || r.status.is_warn()) {
+            for location in &r.locations {
+                let _ = writeln!(s, "{}: {} {}: {}", location, r.status.as_str(), r.id, r.reason);
+            }
         }
         s
     }
 
-    fn print_json(&self, results: &[CheckResult]) { println!("{}", self.render_json(results)); }
+    fn print_text(&self, report: &AuditReport) {
+        println!("{}", self.render_text(report));
+    }
 
-    fn render_json(&self, results: &[CheckResult]) -> String {
-        let out: Vec<_> = results
-            .iter()
-            .filter(|r| self.verbose || !matches!(r.status, Status::Pass | Status::Skip))
-            .cloned()
-            .collect();
-        serde_json::to_string_pretty(&out).unwrap()
+    fn render_text(&self, report: &AuditReport) -> String {
+        text::render(report, &self.text, self.verbose)
     }
 
-    pub fn counts(results: &[CheckResult]) -> (usize, usize, usize, usize) {
-        let pass = results.iter().filter(|r| matches!(r.status, Status::Pass)).count();
-        let warn = results.iter().filter(|r| matches!(r.status, Status::Warn)).count();
-        let fail = results.iter().filter(|r| matches!(r.status, Status::Fail)).count();
-        let skip = results.iter().filter(|r| matches!(r.status, Status::Skip)).count();
+    fn print_json(&self, report: &AuditReport) { println!("{}", Self::render_json(report)); }
+
+    /// Versioned report envelope, see `vps-audit schema`. `--verbose` does not apply here; use `--status` to filter.
+    fn render_json(report: &AuditReport) -> String {
+        serde_json::to_string_pretty(report).unwrap()
+    }
+
+    /// One NDJSON line for a single result: the `CheckResult` fields plus `"type": "result"`.
+    pub fn ndjson_result(result: &CheckResult) -> String {
+        #[derive(Serialize)]
+        struct Line<'a> {
+            #[serde(rename = "type")]
+            kind: &'static str,
+            #[serde(flatten)]
+            result: &'a CheckResult,
+        }
+        serde_json::to_string(&Line { kind: "result", result }).unwrap()
+    }
+
+    /// Final NDJSON line: the report envelope without `results` plus `"type": "summary"`.
+    pub fn ndjson_summary(report: &AuditReport) -> String {
+        #[derive(Serialize)]
+        struct Line<'a> {
+            #[serde(rename = "type")]
+            kind: &'static str,
+            schema_version: u32,
+            tool: &'a ToolInfo,
+            generated_at: &'a str,
+            duration_ms: u64,
+            host: &'a SystemInfo,
+            metrics: &'a HostMetrics,
+            filters: &'a ReportFilters,
+            summary: &'a Summary,
+        }
+        serde_json::to_string(&Line {
+            kind: "summary",
+            schema_version: report.schema_version,
+            tool: &report.tool,
+            generated_at: &report.generated_at,
+            duration_ms: report.duration_ms,
+            host: &report.host,
+            metrics: &report.metrics,
+            filters: &report.filters,
+            summary: &report.summary,
+        })
+        .unwrap()
+    }
+
+    pub fn json_schema() -> String {
+        serde_json::to_string_pretty(&schemars::schema_for!(AuditReport)).unwrap()
+    }
+
+    /// Prometheus text exposition (node_exporter textfile collector) or OpenMetrics when `openmetrics` is set.
+    /// Metrics cover every result that is in the report; `--verbose` only affects the human readable formats.
+    pub fn render_metrics(report: &AuditReport, openmetrics: bool) -> String {
+        let mut s = String::new();
+        let summary = &report.summary;
+        let metrics = &report.metrics;
+
+        metric_header(&mut s, "vps_audit_score", "gauge", "Overall audit score (0-100)");
+        let _ = writeln!(s, "vps_audit_score {}", summary.score);
+
+        metric_header(&mut s, "vps_audit_checks", "gauge", "Number of checks by status");
+        for (status, count) in [("pass", summary.pass), ("warn", summary.warn), ("fail", summary.fail), ("skip", summary.skip)] {
+            let _ = writeln!(s, "vps_audit_checks{{status=\"{}\"}} {}", status, count);
+        }
+
+        metric_header(&mut s, "vps_audit_category_score", "gauge", "Score (0-100) of the checks in each category");
+        for (category, score) in summary.categories.iter().filter_map(|(name, area)| Some((name, area.score?))) {
+            let _ = writeln!(s, "vps_audit_category_score{{category=\"{}\"}} {}", escape_label(category), score);
+        }
+        metric_header(&mut s, "vps_audit_domain_score", "gauge", "Score (0-100) of the checks in each domain (ssh, system, ...)");
+        for (domain, score) in summary.domains.iter().filter_map(|(name, area)| Some((name, area.score?))) {
+            let _ = writeln!(s, "vps_audit_domain_score{{domain=\"{}\"}} {}", escape_label(domain), score);
+        }
+
+        metric_header(&mut s, "vps_audit_check_status", "gauge", "Check status, 1 for the current status of each check and 0 otherwise");
+        for r in &report.results {
+            for status in [Status::Pass, Status::Warn, Status::Fail, Status::Skip] {
+                let _ = writeln!(
+                    s,
+                    "vps_audit_check_status{{id=\"{}\",categories=\"{}\",status=\"{}\"}} {}",
+                    escape_label(&r.id),
+                    escape_label(&r.categories.join(",")),
+                    status.as_str().to_lowercase(),
+                    u8::from(r.status == status)
+                );
+            }
+        }
+
+        metric_header(&mut s, "vps_audit_disk_used_percent", "gauge", "Used disk space across all mounted disks in percent");
+        let _ = writeln!(s, "vps_audit_disk_used_percent {:.2}", metrics.disk_used_percent);
+        metric_header(&mut s, "vps_audit_memory_used_percent", "gauge", "Used memory in percent (based on MemAvailable)");
+        let _ = writeln!(s, "vps_audit_memory_used_percent {:.2}", metrics.memory_used_percent);
+        metric_header(&mut s, "vps_audit_load_ratio", "gauge", "1 minute load average per CPU core");
+        let _ = writeln!(s, "vps_audit_load_ratio {:.2}", metrics.load_ratio);
+        metric_header(&mut s, "vps_audit_listening_ports", "gauge", "Number of listening sockets by exposure");
+        let _ = writeln!(s, "vps_audit_listening_ports{{scope=\"total\"}} {}", metrics.listening_ports_total);
+        let _ = writeln!(s, "vps_audit_listening_ports{{scope=\"public\"}} {}", metrics.listening_ports_public);
+
+        if openmetrics { s.push_str("# EOF\n"); }
+        s
+    }
+
+    pub fn counts<'a>(results: impl IntoIterator<Item = &'a CheckResult>) -> (usize, usize, usize, usize) {
+        let (mut pass, mut warn, mut fail, mut skip) = (0, 0, 0, 0);
+        for r in results {
+            match r.status { Status::Pass => pass += 1, Status::Warn => warn += 1, Status::Fail => fail += 1, Status::Skip => skip += 1 }
+        }
         (pass, warn, fail, skip)
     }
 
-    pub fn score(results: &[CheckResult]) -> u32 {
+    pub fn score<'a>(results: impl IntoIterator<Item = &'a CheckResult>) -> u32 {
         // Simple scoring: each check is equal weight: Pass=1, Warn=0.5, Fail=0, Skip excluded
         let mut total = 0.0f32;
         let mut max = 0.0f32;
@@ -83,4 +234,62 @@ impl Reporter {
     }
 }
 
+pub(crate) fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+fn metric_header(s: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(s, "# HELP {} {}", name, help);
+    let _ = writeln!(s, "# TYPE {} {}", name, kind);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{sample_report, REPORT_SCHEMA_VERSION};
+
+    #[test]
+    fn metrics_have_one_series_per_check_and_status() {
+        let report = sample_report();
+        let metrics = Reporter::render_metrics(&report, false);
+        let series: Vec<&str> = metrics.lines().filter(|l| l.starts_with("vps_audit_check_status{")).collect();
+        assert_eq!(series.len(), report.results.len() * 4);
+        assert!(series.contains(&r#"vps_audit_check_status{id="ssh.root_login",categories="Security",status="fail"} 1"#), "{}", metrics);
+        assert!(series.contains(&r#"vps_audit_check_status{id="ssh.root_login",categories="Security",status="pass"} 0"#));
+        assert!(metrics.contains("vps_audit_checks{status=\"fail\"} 1\n"));
+        assert!(!metrics.contains("# EOF"));
+        assert!(Reporter::render_metrics(&report, true).ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
 
+    #[test]
+    fn json_is_the_versioned_envelope_described_by_the_schema() {
+        let json: serde_json::Value = serde_json::from_str(&Reporter::new(false, OutputFormat::Json).render(&sample_report())).unwrap();
+        assert_eq!(json["schema_version"], REPORT_SCHEMA_VERSION);
+        assert_eq!(json["results"].as_array().unwrap().len(), 3);
+        let schema: serde_json::Value = serde_json::from_str(&Reporter::json_schema()).unwrap();
+        for field in json.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "{} is not in the schema", field);
+        }
+    }
+
+    #[test]
+    fn ndjson_has_one_line_per_result_and_a_summary_last() {
+        let report = sample_report();
+        let lines: Vec<serde_json::Value> = Reporter::new(false, OutputFormat::Ndjson).render(&report).lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), report.results.len() + 1);
+        assert!(lines[..3].iter().all(|l| l["type"] == "result" && l["id"].is_string()));
+        let summary = &lines[3];
+        assert_eq!(summary["type"], "summary");
+        assert_eq!(summary["summary"]["fail"], 1);
+        assert!(summary.get("results").is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use super::escape_xml as esc;
+use crate::model::{AreaScore, AuditReport, EvidenceView, Status};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+pub(crate) const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem auto;max-width:70rem;color:#222}
+table{border-collapse:collapse;width:100%;margin-bottom:1.5rem}th,td{border:1px solid #ddd;padding:.35rem .6rem;text-align:left;vertical-align:top}
+th{background:#f4f4f4}.status{font-weight:bold;border-radius:3px;padding:0 .4rem;color:#fff}
+.PASS{background:#2e7d32}.WARN{background:#ef6c00}.FAIL{background:#c62828}.SKIP{background:#757575}
+pre{white-space:pre-wrap;word-break:break-all;margin:0;font-size:.85em}.muted{color:#666}";
+
+/// Self-contained HTML page (inline CSS, no scripts) suitable for handing to a client.
+pub fn render(report: &AuditReport) -> String {
+    let mut s = String::new();
+    let host = report.host.hostname.as_deref().unwrap_or("unknown host");
+    let _ = writeln!(s, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(s, "<title>VPS audit: {}</title>\n<style>{}</style>\n</head>\n<body>", esc(host), STYLE);
+    let _ = writeln!(s, "<h1>VPS audit: {}</h1>", esc(host));
+    let _ = writeln!(
+        s,
+        "<p class=\"muted\">{} {} &middot; {} &middot; {} &middot; {} ms</p>",
+        esc(&report.tool.name),
+        esc(&report.tool.version),
+        esc(&report.generated_at),
+        esc(report.host.os_pretty_name.as_deref().unwrap_or("unknown OS")),
+        report.duration_ms
+    );
+
+    let summary = &report.summary;
+    let _ = writeln!(s, "<h2>Score: {} / 100</h2>", summary.score);
+    let _ = writeln!(s, "<table>\n<tr><th>PASS</th><th>WARN</th><th>FAIL</th><th>SKIP</th></tr>");
+    let _ = writeln!(s, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n</table>", summary.pass, summary.warn, summary.fail, summary.skip);
+    area_table(&mut s, "Category", &summary.categories);
+    area_table(&mut s, "Domain", &summary.domains);
+    if !report.privileges.root_would_add.is_empty() {
+        let _ = writeln!(s, "<h2>Limited privileges</h2>\n<p>{}; a root run with full capabilities would add:</p>\n<ul>", esc(&report.privileges.limitation()));
+        for (id, what) in &report.privileges.root_would_add { let _ = writeln!(s, "<li><code>{}</code>: {}</li>", esc(id), esc(what)); }
+        let _ = writeln!(s, "</ul>");
+    }
+
+    let _ = writeln!(s, "<h2>Results</h2>\n<table>\n<tr><th>Status</th><th>Check</th><th>Details</th></tr>");
+    for r in &report.results {
+        let _ = writeln!(
+            s,
+            "<tr><td><span class=\"status {}\">{}</span></td><td>{}<br><code class=\"muted\">{}</code></td><td>{}",
+            r.status.as_str(),
+            r.status.as_str(),
+            esc(&r.title),
+            esc(&r.id),
+            esc(&r.reason)
+        );
+        if let Some(remediation) = r.remediation.as_ref().filter(|_| !matches!(r.status, Status::Pass)) {
+            let _ = write!(s, "<br><strong>Remediation:</strong> {}", esc(remediation));
+        }
+        for location in &r.locations {
+            let _ = write!(s, "<br><strong>Location:</strong> <code>{}</code>", esc(&location.to_string()));
+        }
+        let items: Vec<_> = r.findings.iter().filter(|f| !matches!(f.status, Status::Pass)).collect();
+        if !items.is_empty() {
+            let _ = write!(s, "<ul>");
+            for f in items {
+                let _ = write!(s, "<li><span class=\"status {0}\">{0}</span> <code>{1}</code> {2}</li>", f.status.as_str(), esc(&f.subject), esc(&f.reason));
+            }
+            let _ = write!(s, "</ul>");
+        }
+        if let Some(meta) = crate::checks::find(&r.id).map(|c| c.metadata()).filter(|m| !m.rationale.is_empty() && (r.status.is_fail() || r.status.is_warn())) {
+            let _ = write!(s, "<details><summary>Why this matters</summary><p>{}</p>", esc(meta.rationale));
+            if !meta.impact.is_empty() { let _ = write!(s, "<p><strong>Impact of fixing:</strong> {}</p>", esc(meta.impact)); }
+            if !meta.expected.is_empty() { let _ = write!(s, "<p><strong>Expected:</strong> {}</p>", esc(meta.expected)); }
+            for reference in meta.references { let _ = write!(s, "<a href=\"{0}\">{0}</a><br>", esc(reference)); }
+            let _ = write!(s, "</details>");
+        }
+        if let Some(evidence) = &r.evidence { evidence_details(&mut s, evidence.view()); }
+        let _ = writeln!(s, "</td></tr>");
+    }
+    let _ = writeln!(s, "</table>\n</body>\n</html>");
+    s
+}
+
+fn evidence_details(s: &mut String, evidence: &dyn EvidenceView) {
+    let _ = write!(s, "<details><summary>Evidence</summary><dl>");
+    for (label, value) in evidence.fields() { let _ = write!(s, "<dt>{}</dt><dd><code>{}</code></dd>", esc(label), esc(&value)); }
+    let _ = write!(s, "</dl>");
+    if let Some(table) = evidence.table() {
+        let _ = write!(s, "<table><tr>");
+        for column in table.columns { let _ = write!(s, "<th>{}</th>", esc(column)); }
+        let _ = write!(s, "</tr>");
+        for row in &table.rows {
+            let _ = write!(s, "<tr>");
+            for cell in row { let _ = write!(s, "<td>{}</td>", esc(cell)); }
+            let _ = write!(s, "</tr>");
+        }
+        let _ = write!(s, "</table>");
+    }
+    let _ = write!(s, "</details>");
+}
+
+fn area_table(s: &mut String, label: &str, areas: &BTreeMap<String, AreaScore>) {
+    let _ = writeln!(s, "<table>\n<tr><th>{}</th><th>Score</th><th>PASS</th><th>WARN</th><th>FAIL</th><th>SKIP</th></tr>", label);
+    for (name, area) in areas {
+        let score = area.score.map(|v| v.to_string()).unwrap_or_else(|| "n/a".into());
+        let _ = writeln!(
+            s,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            esc(name), score, area.pass, area.warn, area.fail, area.skip
+        );
+    }
+    let _ = writeln!(s, "</table>");
+}
@@ -0,0 +1,69 @@
+use super::escape_xml as esc;
+use crate::model::{AuditReport, CheckResult, Status};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// JUnit XML with one test suite per check domain. FAIL becomes a failure and SKIP a skipped test;
+/// WARN passes but carries its reason in `system-out` so CI stays green on warnings.
+pub fn render(report: &AuditReport) -> String {
+    let mut suites: BTreeMap<&str, Vec<&CheckResult>> = BTreeMap::new();
+    for r in &report.results { suites.entry(r.domain()).or_default().push(r); }
+
+    let mut s = String::new();
+    let _ = writeln!(s, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(
+        s,
+        "<testsuites name=\"vps-audit\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\" timestamp=\"{}\">",
+        report.results.len(),
+        report.results.iter().filter(|r| r.status.is_fail()).count(),
+        report.results.iter().filter(|r| matches!(r.status, Status::Skip)).count(),
+        report.duration_ms as f64 / 1000.0,
+        esc(&report.generated_at)
+    );
+    let host = report.host.hostname.as_deref().unwrap_or("localhost");
+    for (domain, results) in suites {
+        let failures = results.iter().filter(|r| r.status.is_fail()).count();
+        let skipped = results.iter().filter(|r| matches!(r.status, Status::Skip)).count();
+        let _ = writeln!(
+            s,
+            "  <testsuite name=\"{}\" hostname=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+            esc(domain), esc(host), results.len(), failures, skipped
+        );
+        for r in results {
+            let _ = write!(s, "    <testcase name=\"{}\" classname=\"vps-audit.{}\"", esc(&r.id), esc(domain));
+            match r.status {
+                Status::Pass => { let _ = writeln!(s, "/>"); }
+                Status::Fail => {
+                    let _ = writeln!(s, ">");
+                    let _ = writeln!(s, "      <failure message=\"{}\" type=\"FAIL\">{}</failure>", esc(&r.reason), esc(&details(r)));
+                    let _ = writeln!(s, "    </testcase>");
+                }
+                Status::Warn => {
+                    let _ = writeln!(s, ">");
+                    let _ = writeln!(s, "      <system-out>WARN: {}</system-out>", esc(&details(r)));
+                    let _ = writeln!(s, "    </testcase>");
+                }
+                Status::Skip => {
+                    let _ = writeln!(s, ">");
+                    let _ = writeln!(s, "      <skipped message=\"{}\"/>", esc(&r.reason));
+                    let _ = writeln!(s, "    </testcase>");
+                }
+            }
+        }
+        let _ = writeln!(s, "  </testsuite>");
+    }
+    let _ = writeln!(s, "</testsuites>");
+    s
+}
+
+fn details(r: &CheckResult) -> String {
+    let mut d = format!("{}\n{}", r.title, r.reason);
+    if let Some(remediation) = &r.remediation { d.push_str(&format!("\nremediation: {}", remediation)); }
+    for f in r.findings.iter().filter(|f| !matches!(f.status, Status::Pass)) { d.push_str(&format!("\n[{}] {}: {}", f.status.as_str(), f.subject, f.reason)); }
+    if let Some(evidence) = r.evidence.as_ref().map(|e| e.view()) {
+        let fields: Vec<String> = evidence.fields().iter().map(|(label, value)| format!("{}: {}", label, value)).collect();
+        d.push_str(&format!("\nevidence: {}", fields.join(", ")));
+        for row in evidence.table().map(|t| t.rows).unwrap_or_default() { d.push_str(&format!("\n  {}", row.join("  "))); }
+    }
+    d
+}
@@ -0,0 +1,81 @@
+use crate::model::{AreaScore, AuditReport, EvidenceView, Status};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+pub fn render(report: &AuditReport) -> String {
+    let mut s = String::new();
+    let host = report.host.hostname.as_deref().unwrap_or("unknown host");
+    let _ = writeln!(s, "# VPS audit: {}\n", cell(host));
+    let _ = writeln!(
+        s,
+        "{} {}, generated {} on {} ({} ms)\n",
+        report.tool.name,
+        report.tool.version,
+        report.generated_at,
+        cell(report.host.os_pretty_name.as_deref().unwrap_or("unknown OS")),
+        report.duration_ms
+    );
+
+    let summary = &report.summary;
+    let _ = writeln!(s, "## Score: {} / 100\n", summary.score);
+    let _ = writeln!(s, "| PASS | WARN | FAIL | SKIP |\n|---|---|---|---|\n| {} | {} | {} | {} |\n", summary.pass, summary.warn, summary.fail, summary.skip);
+    area_table(&mut s, "Category", &summary.categories);
+    area_table(&mut s, "Domain", &summary.domains);
+
+    if !report.privileges.root_would_add.is_empty() {
+        let _ = writeln!(s, "## Limited privileges\n\n{}; a root run with full capabilities would add:\n", report.privileges.limitation());
+        for (id, what) in &report.privileges.root_would_add { let _ = writeln!(s, "- `{}`: {}", id, cell(what)); }
+        s.push('\n');
+    }
+
+    let _ = writeln!(s, "## Results\n\n| Status | Check | Reason |\n|---|---|---|");
+    for r in &report.results {
+        let _ = writeln!(s, "| {} | {} (`{}`) | {} |", r.status.as_str(), cell(&r.title), r.id, cell(&r.reason));
+    }
+
+    let actionable: Vec<_> = report.results.iter().filter(|r| matches!(r.status, Status::Fail | Status::Warn)).collect();
+    if !actionable.is_empty() {
+        let _ = writeln!(s, "\n## Findings");
+        for r in actionable {
+            let _ = writeln!(s, "\n### [{}] {}\n\n- id: `{}`\n- reason: {}", r.status.as_str(), r.title, r.id, r.reason);
+            if let Some(remediation) = &r.remediation { let _ = writeln!(s, "- remediation: {}", remediation); }
+            for location in &r.locations { let _ = writeln!(s, "- location: `{}`", location); }
+            for f in r.findings.iter().filter(|f| !matches!(f.status, Status::Pass)) {
+                let _ = writeln!(s, "- item: [{}] `{}`: {}", f.status.as_str(), f.subject, f.reason);
+            }
+            if let Some(meta) = crate::checks::find(&r.id).map(|c| c.metadata()) {
+                if !meta.rationale.is_empty() { let _ = writeln!(s, "- why it matters: {}", meta.rationale); }
+                if !meta.impact.is_empty() { let _ = writeln!(s, "- impact of fixing: {}", meta.impact); }
+                if !meta.expected.is_empty() { let _ = writeln!(s, "- expected: {}", meta.expected); }
+                for reference in meta.references { let _ = writeln!(s, "- see <{}>", reference); }
+            }
+            if let Some(evidence) = &r.evidence { evidence_section(&mut s, evidence.view()); }
+        }
+    }
+    s
+}
+
+fn area_table(s: &mut String, label: &str, areas: &BTreeMap<String, AreaScore>) {
+    let _ = writeln!(s, "| {} | Score | PASS | WARN | FAIL | SKIP |\n|---|---|---|---|---|---|", label);
+    for (name, area) in areas {
+        let score = area.score.map(|v| v.to_string()).unwrap_or_else(|| "n/a".into());
+        let _ = writeln!(s, "| {} | {} | {} | {} | {} | {} |", name, score, area.pass, area.warn, area.fail, area.skip);
+    }
+    s.push('\n');
+}
+
+fn evidence_section(s: &mut String, evidence: &dyn EvidenceView) {
+    let _ = writeln!(s, "\n**Evidence**\n");
+    for (label, value) in evidence.fields() { let _ = writeln!(s, "- {}: {}", label, value); }
+    if let Some(table) = evidence.table() {
+        let _ = writeln!(s, "\n| {} |\n|{}", table.columns.join(" | "), "---|".repeat(table.columns.len()));
+        for row in &table.rows {
+            let _ = writeln!(s, "| {} |", row.iter().map(|c| cell(c)).collect::<Vec<_>>().join(" | "));
+        }
+    }
+}
+
+/// Keeps table cells on one line and stops `|` from splitting them.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
@@ -0,0 +1,93 @@
+use crate::model::{AuditReport, CheckResult, Finding, Location, Status};
+use serde_json::{json, Value};
+
+/// SARIF 2.1.0 log with one rule per check and one result per check result, or per WARN/FAIL finding.
+pub fn render(report: &AuditReport) -> String {
+    let rules: Vec<Value> = report
+        .results
+        .iter()
+        .map(|r| {
+            json!({
+                "id": r.id,
+                "name": r.id,
+                "shortDescription": {"text": r.title},
+                "help": {"text": r.remediation.clone().unwrap_or_default()},
+                "properties": {"tags": r.categories},
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = report
+        .results
+        .iter()
+        .enumerate()
+        .flat_map(|(index, r)| {
+            let items: Vec<&Finding> = r.findings.iter().filter(|f| f.status.is_fail() || f.status.is_warn()).collect();
+            if items.is_empty() {
+                let mut result = result(r, index, r.status, &r.reason);
+                if let Some(evidence) = &r.evidence {
+                    result["properties"] = json!({"evidence": evidence});
+                }
+                if !r.locations.is_empty() {
+                    result["locations"] = r.locations.iter().map(location).collect();
+                }
+                return vec![result];
+            }
+            // One result per failing item so code scanning tracks and dismisses them individually
+            items
+                .into_iter()
+                .map(|f| {
+                    let mut result = result(r, index, f.status, &format!("{}: {}", f.subject, f.reason));
+                    result["partialFingerprints"] = json!({"subject": f.subject});
+                    result["properties"] = json!({"subject": f.subject, "evidence": f.evidence});
+                    if f.subject.starts_with('/') {
+                        result["locations"] = json!([location(&Location::file(&f.subject))]);
+                    }
+                    result
+                })
+                .collect()
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {"driver": {
+                "name": report.tool.name,
+                "version": report.tool.version,
+                "informationUri": "https://github.com/milangress/vps-audit",
+                "rules": rules,
+            }},
+            "invocations": [{"executionSuccessful": true, "startTimeUtc": report.generated_at}],
+            "properties": {"host": report.host, "summary": report.summary},
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap()
+}
+
+fn result(r: &CheckResult, index: usize, status: Status, message: &str) -> Value {
+    let (kind, level) = match status {
+        Status::Pass => ("pass", "none"),
+        Status::Warn => ("fail", "warning"),
+        Status::Fail => ("fail", "error"),
+        Status::Skip => ("notApplicable", "none"),
+    };
+    json!({
+        "ruleId": r.id,
+        "ruleIndex": index,
+        "kind": kind,
+        "level": level,
+        "message": {"text": message},
+    })
+}
+
+fn location(l: &Location) -> Value {
+    let mut physical = json!({"artifactLocation": {"uri": format!("file://{}", l.path)}});
+    if let Some(line) = l.line {
+        physical["region"] = json!({"startLine": line});
+        if let Some(text) = &l.text { physical["region"]["snippet"] = json!({"text": text}); }
+    }
+    json!({"physicalLocation": physical})
+}
@@ -0,0 +1,278 @@
+use crate::model::{AreaScore, AuditCheck, AuditReport, CheckResult, EvidenceView, Status};
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice { Auto, Always, Never }
+
+impl ColorChoice {
+    /// `auto` enables color only when stdout is a terminal and `NO_COLOR` is unset or empty.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy { None, Category, Domain }
+
+#[derive(Clone, Debug)]
+pub struct TextOptions {
+    pub color: bool,
+    pub group_by: GroupBy,
+    /// One line per check instead of the multi-line block
+    pub compact: bool,
+    /// Wrap (or truncate in compact mode) at this many columns
+    pub width: Option<usize>,
+}
+
+impl Default for TextOptions {
+    /// Plain, ungrouped and unwrapped; what gets written to files.
+    fn default() -> Self { Self { color: false, group_by: GroupBy::None, compact: false, width: None } }
+}
+
+/// Width of the terminal attached to stdout, falling back to `$COLUMNS`.
+pub fn terminal_width() -> Option<usize> {
+    if let Some((terminal_size::Width(w), _)) = terminal_size::terminal_size() {
+        return Some(w as usize);
+    }
+    std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok())
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[1;31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+
+struct Painter { enabled: bool }
+
+impl Painter {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.enabled { format!("{}{}{}", style, text, RESET) } else { text.to_string() }
+    }
+
+    fn status(&self, status: Status) -> String {
+        let style = match status { Status::Pass => GREEN, Status::Warn => YELLOW, Status::Fail => RED, Status::Skip => DIM };
+        self.paint(style, status.as_str())
+    }
+
+    fn score(&self, score: u32) -> String {
+        let style = if score >= 80 { GREEN } else if score >= 50 { YELLOW } else { RED };
+        self.paint(style, &score.to_string())
+    }
+}
+
+pub fn render(report: &AuditReport, options: &TextOptions, verbose: bool) -> String {
+    let p = Painter { enabled: options.color };
+    let mut s = String::new();
+    s.push_str(&p.paint(BOLD, "VPS Audit Results"));
+    s.push('\n');
+    s.push_str("=================\n");
+    let summary = &report.summary;
+    s.push_str(&format!("Score: {} / 100\n", p.score(summary.score)));
+    s.push_str(&format!(
+        "{}={}, {}={}, {}={}, {}={}\n",
+        p.status(Status::Pass), summary.pass, p.status(Status::Warn), summary.warn, p.status(Status::Fail), summary.fail, p.status(Status::Skip), summary.skip
+    ));
+    s.push_str(&format!("Category scores: {}\n", area_line(&p, &summary.categories)));
+    s.push_str(&format!("Domain scores: {}\n", area_line(&p, &summary.domains)));
+    if let Some((name, area)) = summary.weakest_area() {
+        s.push_str(&format!("Weakest area: {} ({} / 100)\n", name, p.score(area.score.unwrap_or_default())));
+    }
+    let privileges = &report.privileges;
+    if !privileges.root_would_add.is_empty() {
+        s.push_str(&format!("{}; a root run with full capabilities would add:\n", privileges.limitation()));
+        for (id, what) in &privileges.root_would_add { s.push_str(&format!("  {}: {}\n", id, what)); }
+    }
+    s.push('\n');
+
+    let visible = |r: &&CheckResult| verbose || !matches!(r.status, Status::Pass | Status::Skip);
+    match options.group_by {
+        GroupBy::None => {
+            for r in report.results.iter().filter(visible) { render_result(&mut s, &p, options, r); }
+        }
+        GroupBy::Category | GroupBy::Domain => {
+            let mut groups: BTreeMap<&str, Vec<&CheckResult>> = BTreeMap::new();
+            for r in &report.results {
+                // A check is listed once, under its primary (first) category
+                let key = match options.group_by {
+                    GroupBy::Category => r.categories.first().map(String::as_str).unwrap_or("uncategorized"),
+                    _ => r.domain(),
+                };
+                groups.entry(key).or_default().push(r);
+            }
+            for (name, results) in groups {
+                let (pass, warn, fail, skip) = super::Reporter::counts(results.iter().copied());
+                let mut counts = Vec::new();
+                for (status, n) in [(Status::Fail, fail), (Status::Warn, warn), (Status::Pass, pass), (Status::Skip, skip)] {
+                    if n > 0 { counts.push(format!("{} {}", n, p.status(status))); }
+                }
+                s.push_str(&format!("{} ({})\n", p.paint(BOLD, &format!("== {}", name)), counts.join(", ")));
+                let shown: Vec<_> = results.into_iter().filter(|r| visible(r)).collect();
+                for r in &shown { render_result(&mut s, &p, options, r); }
+                if options.compact || shown.is_empty() { s.push('\n'); }
+            }
+        }
+    }
+    s
+}
+
+/// `vps-audit explain <id>`: static documentation of one check.
+pub fn explain(check: &dyn AuditCheck, options: &TextOptions) -> String {
+    let p = Painter { enabled: options.color };
+    let meta = check.metadata();
+    let mut s = format!("{}\n", p.paint(BOLD, check.title()));
+    push_field(&mut s, options, "id", check.id());
+    push_field(&mut s, options, "categories", &check.categories().join(", "));
+    if !meta.expected.is_empty() { push_field(&mut s, options, "expected", meta.expected); }
+    if !meta.rationale.is_empty() { push_field(&mut s, options, "why it matters", meta.rationale); }
+    if !meta.impact.is_empty() { push_field(&mut s, options, "impact of fixing", meta.impact); }
+    let requires: Vec<String> = check.prerequisites().iter().map(|p| p.describe()).collect();
+    if !requires.is_empty() { push_field(&mut s, options, "requires", &requires.join(", ")); }
+    let mut controls: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (framework, control) in check.controls() { controls.entry(framework.key()).or_default().push(control); }
+    for (framework, ids) in controls { push_field(&mut s, options, framework, &ids.join(", ")); }
+    for reference in meta.references { push_field(&mut s, options, "see", reference); }
+    s
+}
+
+fn render_result(s: &mut String, p: &Painter, options: &TextOptions, r: &CheckResult) {
+    if options.compact {
+        // Status is always four characters wide, so the visible prefix length is independent of color codes
+        let line = format!("{}  {}", r.id, r.reason);
+        let line = match options.width { Some(w) => truncate(&line, w.saturating_sub(7)), None => line };
+        s.push_str(&format!("[{}] {}\n", p.status(r.status), line));
+        return;
+    }
+    s.push_str(&format!("[{}] {}\n", p.status(r.status), p.paint(BOLD, &r.title)));
+    push_field(s, options, "id", &r.id);
+    if !r.categories.is_empty() { push_field(s, options, "categories", &r.categories.join(", ")); }
+    push_field(s, options, "reason", &r.reason);
+    if let Some(remediation) = &r.remediation { push_field(s, options, "remediation", remediation); }
+    if let Some(needs_root) = &r.needs_root { push_field(s, options, "as root", needs_root); }
+    for location in &r.locations {
+        match &location.text {
+            Some(text) => push_field(s, options, "location", &format!("{}: {}", location, text)),
+            None => push_field(s, options, "location", &location.to_string()),
+        }
+    }
+    for f in r.findings.iter().filter(|f| !matches!(f.status, Status::Pass)) {
+        push_field(s, options, "item", &format!("[{}] {}: {}", p.status(f.status), f.subject, f.reason));
+    }
+    if let Some(evidence) = &r.evidence { push_evidence(s, options, evidence.view()); }
+    s.push('\n');
+}
+
+/// Evidence fields as one field, followed by its table (ports, filesystems) aligned under the value.
+fn push_evidence(s: &mut String, options: &TextOptions, evidence: &dyn EvidenceView) {
+    let fields: Vec<String> = evidence.fields().iter().map(|(label, value)| format!("{}: {}", label, value)).collect();
+    push_field(s, options, "evidence", &fields.join(", "));
+    let Some(table) = evidence.table() else { return };
+    let indent = "  evidence: ".len();
+    let header: Vec<String> = table.columns.iter().map(|c| c.to_uppercase()).collect();
+    let mut widths: Vec<usize> = header.iter().map(|c| c.chars().count()).collect();
+    for row in &table.rows {
+        for (width, cell) in widths.iter_mut().zip(row) { *width = (*width).max(cell.chars().count()); }
+    }
+    for row in std::iter::once(&header).chain(&table.rows) {
+        let line: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<1$}", cell, width)).collect();
+        let line = line.join("  ").trim_end().to_string();
+        let line = match options.width { Some(w) => truncate(&line, w.saturating_sub(indent)), None => line };
+        s.push_str(&format!("{}{}\n", " ".repeat(indent), line));
+    }
+}
+
+/// `  label: value`, wrapped with a hanging indent when a width is known.
+fn push_field(s: &mut String, options: &TextOptions, label: &str, value: &str) {
+    let prefix = format!("  {}: ", label);
+    match options.width {
+        Some(width) if width > prefix.len() + 20 => {
+            let lines = wrap(value, width - prefix.len());
+            for (i, line) in lines.iter().enumerate() {
+                if i == 0 { s.push_str(&prefix); } else { s.push_str(&" ".repeat(prefix.len())); }
+                s.push_str(line);
+                s.push('\n');
+            }
+        }
+        _ => s.push_str(&format!("{}{}\n", prefix, value)),
+    }
+}
+
+/// Greedy word wrap; words longer than `width` (e.g. compact JSON) are split hard.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while !word.is_empty() {
+            let used = current.chars().count();
+            let sep = usize::from(used > 0);
+            if used + sep + word.len() <= width {
+                if sep == 1 { current.push(' '); }
+                current.extend(word.drain(..));
+            } else if used > 0 {
+                lines.push(std::mem::take(&mut current));
+            } else {
+                current.extend(word.drain(..width));
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() || lines.is_empty() { lines.push(current); }
+    lines
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width { return text.to_string(); }
+    let mut out: String = text.chars().take(width.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+fn area_line(p: &Painter, areas: &BTreeMap<String, AreaScore>) -> String {
+    areas
+        .iter()
+        .map(|(name, area)| match area.score { Some(score) => format!("{} {}", name, p.score(score)), None => format!("{} n/a", name) })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_report;
+
+    #[test]
+    fn wrap_breaks_at_words_and_splits_long_ones() {
+        assert_eq!(wrap("one two three", 7), ["one two", "three"]);
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("", 10), [""]);
+        assert_eq!(truncate("abcdef", 4), "abc…");
+    }
+
+    #[test]
+    fn groups_list_counts_and_compact_lines_fit_the_width() {
+        let options = TextOptions { color: false, group_by: GroupBy::Domain, compact: true, width: Some(30) };
+        let out = render(&sample_report(), &options, false);
+        assert!(out.contains("== ssh (1 FAIL, 1 PASS)\n[FAIL] ssh.root_login  Permit…\n"), "{}", out);
+        assert!(out.contains("== system (1 WARN)\n"));
+        assert!(!out.contains("ssh.password_auth"));
+        assert!(!out.contains('\x1b'));
+        assert!(out.lines().filter(|l| l.starts_with('[')).all(|l| l.chars().count() <= 30), "{}", out);
+    }
+
+    #[test]
+    fn explain_lists_metadata_and_controls_per_framework() {
+        let check = crate::checks::find("ssh.root_login").unwrap();
+        let out = explain(check.as_ref(), &TextOptions { color: false, ..TextOptions::default() });
+        assert!(out.starts_with("SSH root login is disabled\n  id: ssh.root_login\n"), "{}", out);
+        assert!(out.contains("  expected: PermitRootLogin no"));
+        assert!(out.contains("  nist-800-53: AC-6(2), AC-17\n"), "{}", out);
+        assert!(out.contains("  see: https://man.openbsd.org/sshd_config#PermitRootLogin\n"));
+    }
+}
@@ -0,0 +1,257 @@
+//! `--sandbox`: once arguments are parsed and keys loaded, the audit confines itself before collecting anything.
+//! Landlock makes the filesystem read-only (except the `--output` directories and the scan cache) and only lets the
+//! external helpers execute; a seccomp filter allows the system calls collectors and checks need and fails everything
+//! else with EPERM. Both are inherited by helper processes and cannot be lifted, so a compromised parser cannot modify
+//! the host.
+use crate::exec;
+use crate::model::SandboxSummary;
+use crate::trace;
+use anyhow::{bail, Context};
+use landlock::{
+    Access, AccessFs, AccessNet, LandlockStatus, PathBeneath, PathFd, Ruleset, RulesetAttr, RulesetCreatedAttr, RulesetStatus, ABI,
+};
+use seccompiler::{SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter, SeccompRule};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Newest Landlock ABI the rules were written for; older kernels enforce what they support.
+const LANDLOCK_ABI: ABI = ABI::V5;
+
+static APPLIED: OnceLock<SandboxSummary> = OnceLock::new();
+
+/// Restrictions in effect, recorded in every report produced afterwards.
+pub fn status() -> Option<SandboxSummary> {
+    APPLIED.get().cloned()
+}
+
+/// Confines the whole process; `writable` are the directories reports and the scan cache are written to. Must run
+/// before any thread is started, since Landlock only applies to the calling thread and the ones it creates later.
+pub fn apply(writable: &[PathBuf]) -> anyhow::Result<()> {
+    let mut writable: Vec<PathBuf> = writable.iter().map(|d| d.canonicalize().with_context(|| format!("output directory {}", d.display()))).collect::<Result<_, _>>()?;
+    writable.sort();
+    writable.dedup();
+    let executable = executables();
+    let abi = landlock(&writable, &executable)?;
+    let allowed_syscalls = seccomp(!writable.is_empty(), !executable.is_empty())?;
+    let summary = SandboxSummary {
+        landlock_abi: abi,
+        allowed_syscalls,
+        writable: writable.iter().map(|p| p.display().to_string()).collect(),
+        executable: executable.iter().map(|p| p.display().to_string()).collect(),
+    };
+    trace::event("sandbox", || format!("Landlock ABI {}, {} system calls allowed, writable {:?}, executable {:?}", abi, allowed_syscalls, summary.writable, summary.executable));
+    let _ = APPLIED.set(summary);
+    Ok(())
+}
+
+/// Helpers `exec` may run plus the dynamic loaders they need, nothing when `--no-exec` is given.
+fn executables() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for program in exec::HELPERS.iter().filter(|_| !exec::disabled()).filter_map(|p| exec::resolve(p)) {
+        let Ok(program) = program.canonicalize() else { continue };
+        // The kernel opens the ELF interpreter with execute permission too
+        if let Some(interpreter) = interpreter(&program).and_then(|i| i.canonicalize().ok()) { paths.push(interpreter); }
+        paths.push(program);
+    }
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// `PT_INTERP` of a 64-bit little-endian ELF executable.
+fn interpreter(path: &Path) -> Option<PathBuf> {
+    let elf = std::fs::read(path).ok()?;
+    if elf.get(..6)? != b"\x7fELF\x02\x01" { return None; }
+    let u16_at = |at: usize| Some(u16::from_le_bytes(elf.get(at..at + 2)?.try_into().ok()?) as usize);
+    let u64_at = |at: usize| Some(u64::from_le_bytes(elf.get(at..at + 8)?.try_into().ok()?) as usize);
+    let (phoff, phentsize, phnum) = (u64_at(0x20)?, u16_at(0x36)?, u16_at(0x38)?);
+    (0..phnum).map(|i| phoff + i * phentsize).find_map(|ph| {
+        if elf.get(ph..ph + 4)? != 3u32.to_le_bytes() { return None; }
+        let (offset, size) = (u64_at(ph + 0x08)?, u64_at(ph + 0x20)?);
+        let name = elf.get(offset..offset + size)?.split(|&b| b == 0).next()?;
+        Some(PathBuf::from(String::from_utf8_lossy(name).into_owned()))
+    })
+}
+
+/// Returns the Landlock ABI the rules are enforced at: the kernel's, capped at [`LANDLOCK_ABI`] since newer access
+/// rights are not handled. Fails when the kernel enforces nothing.
+fn landlock(writable: &[PathBuf], executable: &[PathBuf]) -> anyhow::Result<u8> {
+    let read = AccessFs::ReadFile | AccessFs::ReadDir;
+    let write = read | AccessFs::WriteFile | AccessFs::MakeReg | AccessFs::RemoveFile;
+    let mut ruleset = Ruleset::default()
+        .handle_access(AccessFs::from_all(LANDLOCK_ABI))?
+        // No TCP rules at all: connecting and binding are denied where the kernel supports it
+        .handle_access(AccessNet::from_all(LANDLOCK_ABI))?
+        .create()?
+        .add_rule(PathBeneath::new(PathFd::new("/")?, read))?;
+    for dir in writable { ruleset = ruleset.add_rule(PathBeneath::new(PathFd::new(dir)?, write))?; }
+    for file in executable { ruleset = ruleset.add_rule(PathBeneath::new(PathFd::new(file)?, AccessFs::ReadFile | AccessFs::Execute))?; }
+    let status = ruleset.restrict_self().context("failed to apply the Landlock ruleset")?;
+    match (status.ruleset, status.landlock) {
+        (RulesetStatus::NotEnforced, LandlockStatus::NotEnabled) => bail!("Landlock is not enabled in this kernel (boot with lsm=...,landlock)"),
+        (RulesetStatus::NotEnforced, _) => bail!("Landlock is not supported by this kernel (Linux 5.13 or later is required)"),
+        (_, LandlockStatus::Available { effective_abi, .. }) => Ok(effective_abi.min(LANDLOCK_ABI) as u8),
+        (_, status) => bail!("unexpected Landlock status {:?}", status),
+    }
+}
+
+/// Installs the filter on all threads and returns the number of allowed system calls.
+fn seccomp(write_outputs: bool, run_helpers: bool) -> anyhow::Result<usize> {
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
+    for syscall in READ_ONLY { rules.insert(*syscall, vec![]); }
+    if write_outputs {
+        for syscall in WRITE_OUTPUTS { rules.insert(*syscall, vec![]); }
+        rules.insert(libc::SYS_openat, vec![]);
+    } else {
+        // Defense in depth should Landlock be partially enforced: no open for writing at all
+        let mask = (libc::O_WRONLY | libc::O_RDWR | libc::O_CREAT | libc::O_TRUNC) as u64;
+        let read_only = |arg| -> anyhow::Result<Vec<SeccompRule>> {
+            Ok(vec![SeccompRule::new(vec![SeccompCondition::new(arg, SeccompCmpArgLen::Dword, SeccompCmpOp::MaskedEq(mask), 0)?])?])
+        };
+        rules.insert(libc::SYS_openat, read_only(2)?);
+        #[cfg(target_arch = "x86_64")]
+        rules.insert(libc::SYS_open, read_only(1)?);
+    }
+    if run_helpers {
+        for syscall in RUN_HELPERS { rules.insert(*syscall, vec![]); }
+    }
+    let count = rules.len();
+    let arch = std::env::consts::ARCH.try_into().context("seccomp is not supported on this architecture")?;
+    let filter = SeccompFilter::new(rules, SeccompAction::Errno(libc::EPERM as u32), SeccompAction::Allow, arch)?;
+    let program: seccompiler::BpfProgram = filter.try_into()?;
+    seccompiler::apply_filter_all_threads(&program).context("failed to install the seccomp filter")?;
+    Ok(count)
+}
+
+/// Reading files and `/proc`, threads, memory, time and writing to already open descriptors (stdout, stderr).
+const READ_ONLY: &[i64] = &[
+    libc::SYS_read,
+    libc::SYS_readv,
+    libc::SYS_pread64,
+    libc::SYS_write,
+    libc::SYS_writev,
+    libc::SYS_close,
+    libc::SYS_fstat,
+    libc::SYS_newfstatat,
+    libc::SYS_statx,
+    libc::SYS_statfs,
+    libc::SYS_fstatfs,
+    libc::SYS_lseek,
+    libc::SYS_getdents64,
+    libc::SYS_readlinkat,
+    libc::SYS_faccessat,
+    libc::SYS_faccessat2,
+    libc::SYS_fcntl,
+    libc::SYS_ioctl,
+    libc::SYS_ppoll,
+    libc::SYS_getcwd,
+    libc::SYS_mmap,
+    libc::SYS_munmap,
+    libc::SYS_mremap,
+    libc::SYS_mprotect,
+    libc::SYS_madvise,
+    libc::SYS_brk,
+    libc::SYS_rt_sigaction,
+    libc::SYS_rt_sigprocmask,
+    libc::SYS_rt_sigreturn,
+    libc::SYS_sigaltstack,
+    libc::SYS_futex,
+    libc::SYS_set_robust_list,
+    libc::SYS_rseq,
+    libc::SYS_clone,
+    libc::SYS_clone3,
+    libc::SYS_prctl,
+    libc::SYS_sched_getaffinity,
+    libc::SYS_sched_yield,
+    libc::SYS_getrandom,
+    libc::SYS_clock_gettime,
+    libc::SYS_clock_nanosleep,
+    libc::SYS_nanosleep,
+    libc::SYS_gettimeofday,
+    libc::SYS_getpid,
+    libc::SYS_gettid,
+    libc::SYS_getuid,
+    libc::SYS_geteuid,
+    libc::SYS_getgid,
+    libc::SYS_getegid,
+    libc::SYS_capget,
+    libc::SYS_uname,
+    libc::SYS_sysinfo,
+    libc::SYS_prlimit64,
+    libc::SYS_getrusage,
+    libc::SYS_tgkill,
+    libc::SYS_exit,
+    libc::SYS_exit_group,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_stat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_lstat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_access,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_readlink,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_poll,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_arch_prctl,
+];
+
+/// `output::write_atomic`: a temporary file renamed into place. Landlock limits these to the output directories.
+const WRITE_OUTPUTS: &[i64] = &[
+    libc::SYS_fchmod,
+    libc::SYS_fsync,
+    libc::SYS_fdatasync,
+    libc::SYS_renameat,
+    libc::SYS_renameat2,
+    libc::SYS_unlinkat,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_rename,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_unlink,
+];
+
+/// `exec::run` and the helpers themselves. Landlock limits execve to [`exec::HELPERS`].
+const RUN_HELPERS: &[i64] = &[
+    libc::SYS_execve,
+    libc::SYS_pipe2,
+    libc::SYS_dup3,
+    libc::SYS_setpgid,
+    libc::SYS_wait4,
+    libc::SYS_waitid,
+    libc::SYS_kill,
+    libc::SYS_set_tid_address,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_vfork,
+    #[cfg(target_arch = "x86_64")]
+    libc::SYS_dup2,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpreter_comes_from_the_elf_program_headers() {
+        // The test binary itself is a dynamically linked ELF
+        let loader = interpreter(&std::env::current_exe().unwrap()).expect("PT_INTERP");
+        assert!(loader.is_absolute() && loader.exists(), "{}", loader.display());
+
+        let script = std::env::temp_dir().join(format!("vps-audit-sandbox-{}.sh", std::process::id()));
+        std::fs::write(&script, "#!/bin/sh\ntrue\n").unwrap();
+        assert_eq!(interpreter(&script), None);
+        let _ = std::fs::remove_file(script);
+    }
+
+    #[test]
+    fn syscall_groups_do_not_overlap() {
+        let mut all: Vec<i64> = READ_ONLY.iter().chain(WRITE_OUTPUTS).chain(RUN_HELPERS).copied().collect();
+        let total = all.len();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), total);
+        // openat is added separately, restricted to reading unless outputs are written
+        assert!(!all.contains(&libc::SYS_openat));
+        assert!(!READ_ONLY.contains(&libc::SYS_execve));
+    }
+}
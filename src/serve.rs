@@ -0,0 +1,150 @@
+use crate::engine::AuditEngine;
+use crate::model::AuditReport;
+use crate::notify::Notifier;
+use crate::redact::Redactor;
+use regex::Regex;
+use crate::report::{OutputFormat, Reporter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+pub struct ServeOptions {
+    pub listen: String,
+    /// Maximum age of a cached audit before it is re-run
+    pub refresh: Duration,
+    /// Re-run in the background every `refresh` instead of lazily on scrape
+    pub schedule: bool,
+    /// Redact every snapshot with the built-in and these extra patterns
+    pub redact: Option<Vec<Regex>>,
+    /// Notify configured sinks after every audit run
+    pub notifier: Option<Notifier>,
+}
+
+struct Snapshot {
+    taken_at: Instant,
+    report: AuditReport,
+}
+
+/// Read-only HTTP endpoint exposing `/metrics`, `/report.json` and `/healthz`.
+pub struct AuditServer {
+    engine: Arc<AuditEngine>,
+    options: Arc<ServeOptions>,
+    latest: Arc<Mutex<Option<Snapshot>>>,
+    /// One redactor for the server lifetime, so a value keeps its pseudonym across snapshots
+    redactor: Arc<Mutex<Option<Redactor>>>,
+    /// A lazy refresh is running in the background
+    refreshing: Arc<AtomicBool>,
+}
+
+impl AuditServer {
+    pub fn new(engine: AuditEngine, options: ServeOptions) -> Self {
+        let redactor = options.redact.as_ref().map(|patterns| Redactor::new(patterns));
+        Self {
+            engine: Arc::new(engine),
+            options: Arc::new(options),
+            latest: Arc::new(Mutex::new(None)),
+            redactor: Arc::new(Mutex::new(redactor)),
+            refreshing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn run(&self) -> anyhow::Result<()> {
+        let server = Server::http(&self.options.listen).map_err(|e| anyhow::anyhow!("failed to listen on {}: {}", self.options.listen, e))?;
+        eprintln!("vps-audit serving on http://{} (/metrics, /report.json, /healthz)", self.options.listen);
+
+        // Populate the cache before accepting scrapes so the first request does not pay for a full audit
+        refresh(&self.engine, &self.latest, &self.redactor, &self.options);
+        if self.options.schedule {
+            let engine = Arc::clone(&self.engine);
+            let latest = Arc::clone(&self.latest);
+            let redactor = Arc::clone(&self.redactor);
+            let options = Arc::clone(&self.options);
+            thread::spawn(move || loop {
+                thread::sleep(options.refresh);
+                refresh(&engine, &latest, &redactor, &options);
+            });
+        }
+
+        for request in server.incoming_requests() {
+            self.handle(request);
+        }
+        Ok(())
+    }
+
+    fn handle(&self, request: Request) {
+        if !matches!(request.method(), Method::Get | Method::Head) {
+            let response = Response::from_string("method not allowed\n").with_status_code(405).with_header(header("Allow", "GET, HEAD"));
+            let _ = request.respond(response);
+            return;
+        }
+        let path = request.url().split('?').next().unwrap_or("/").to_string();
+        let response = match path.as_str() {
+            "/metrics" => {
+                let body = self.with_snapshot(|snap| Reporter::render_metrics(&snap.report, true));
+                Response::from_string(body).with_header(header("Content-Type", "application/openmetrics-text; version=1.0.0; charset=utf-8"))
+            }
+            "/report.json" => {
+                let reporter = Reporter::new(true, OutputFormat::Json);
+                let body = self.with_snapshot(|snap| reporter.render(&snap.report));
+                Response::from_string(body).with_header(header("Content-Type", "application/json"))
+            }
+            "/healthz" => Response::from_string("ok\n").with_header(header("Content-Type", "text/plain; charset=utf-8")),
+            _ => Response::from_string("not found\n").with_status_code(404),
+        };
+        let _ = request.respond(response);
+    }
+
+    /// Runs `f` on the latest snapshot. In lazy mode a stale snapshot starts one refresh in the background and is
+    /// served until the new one is ready, so a full audit never blocks the request loop or a scrape.
+    fn with_snapshot<T>(&self, f: impl FnOnce(&Snapshot) -> T) -> T {
+        if !self.options.schedule {
+            let stale = self.latest.lock().unwrap().as_ref().map(|s| s.taken_at.elapsed() >= self.options.refresh).unwrap_or(true);
+            if stale && !self.refreshing.swap(true, Ordering::AcqRel) {
+                let engine = Arc::clone(&self.engine);
+                let latest = Arc::clone(&self.latest);
+                let redactor = Arc::clone(&self.redactor);
+                let options = Arc::clone(&self.options);
+                let refreshing = Arc::clone(&self.refreshing);
+                thread::spawn(move || {
+                    refresh(&engine, &latest, &redactor, &options);
+                    refreshing.store(false, Ordering::Release);
+                });
+            }
+        }
+        let guard = self.latest.lock().unwrap();
+        f(guard.as_ref().expect("audit snapshot is populated before serving"))
+    }
+}
+
+fn refresh(engine: &AuditEngine, latest: &Mutex<Option<Snapshot>>, redactor: &Mutex<Option<Redactor>>, options: &ServeOptions) {
+    let mut report = engine.audit();
+    let mut redactor = redactor.lock().unwrap();
+    // Notifications compare real issue keys across runs and only redact what they send
+    if let Some(notifier) = &options.notifier {
+        if let Err(e) = notifier.notify(&report, redactor.as_mut()) { eprintln!("error: {:#}", e); }
+    }
+    if let Some(redactor) = redactor.as_mut() { redactor.redact_report(&mut report); }
+    *latest.lock().unwrap() = Some(Snapshot { taken_at: Instant::now(), report });
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("static header is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pseudonyms_survive_refreshes() {
+        let options = ServeOptions { listen: String::new(), refresh: Duration::ZERO, schedule: false, redact: Some(Vec::new()), notifier: None };
+        let server = AuditServer::new(AuditEngine::new(None), options);
+        let seen = server.redactor.lock().unwrap().as_mut().unwrap().redact_hostname("earlier-snapshot");
+        assert_eq!(seen, "host-1");
+        refresh(&server.engine, &server.latest, &server.redactor, &server.options);
+        let hostname = server.latest.lock().unwrap().as_ref().unwrap().report.host.hostname.clone();
+        if let Some(hostname) = hostname { assert_eq!(hostname, "host-2"); }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::model::{AuditReport, ReportSignature};
+use anyhow::{anyhow, bail, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde_json::Value;
+use std::path::Path;
+
+pub const ALGORITHM: &str = "ed25519";
+
+/// Loads a PKCS#8 PEM private key (`openssl genpkey -algorithm ed25519`) or a base64 encoded 32 byte seed.
+pub fn load_signing_key(path: &Path) -> anyhow::Result<SigningKey> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("failed to read signing key {}", path.display()))?;
+    if content.contains("-----BEGIN") {
+        return SigningKey::from_pkcs8_pem(&content).map_err(|e| anyhow!("invalid PKCS#8 ed25519 key in {}: {}", path.display(), e));
+    }
+    let seed: [u8; 32] = decode_raw_key(&content).with_context(|| format!("invalid signing key in {}", path.display()))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Loads a SPKI PEM public key (`openssl pkey -pubout`) or a base64 encoded 32 byte key.
+pub fn load_verifying_key(path: &Path) -> anyhow::Result<VerifyingKey> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("failed to read public key {}", path.display()))?;
+    if content.contains("-----BEGIN") {
+        return VerifyingKey::from_public_key_pem(&content).map_err(|e| anyhow!("invalid ed25519 public key in {}: {}", path.display(), e));
+    }
+    let bytes: [u8; 32] = decode_raw_key(&content).with_context(|| format!("invalid public key in {}", path.display()))?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| anyhow!("invalid ed25519 public key in {}: {}", path.display(), e))
+}
+
+/// Public key as embedded in [`ReportSignature::public_key`].
+pub fn decode_verifying_key(base64: &str) -> anyhow::Result<VerifyingKey> {
+    let bytes: [u8; 32] = decode_raw_key(base64).context("invalid embedded public key")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| anyhow!("invalid ed25519 public key: {}", e))
+}
+
+fn decode_raw_key(content: &str) -> anyhow::Result<[u8; 32]> {
+    let bytes = BASE64.decode(content.trim()).context("expected PEM or base64")?;
+    bytes.try_into().map_err(|b: Vec<u8>| anyhow!("expected 32 bytes, got {}", b.len()))
+}
+
+/// Signs the canonical serialization of `report` (without its `signature` field) and embeds the signature.
+pub fn sign_report(report: &mut AuditReport, key: &SigningKey) {
+    report.signature = None;
+    let value = serde_json::to_value(&*report).expect("report serializes to JSON");
+    let signature = key.sign(canonical_json(&value).as_bytes());
+    report.signature = Some(ReportSignature {
+        algorithm: ALGORITHM.into(),
+        public_key: BASE64.encode(key.verifying_key().as_bytes()),
+        value: BASE64.encode(signature.to_bytes()),
+    });
+}
+
+/// Checks the embedded signature of a JSON report against `key`. Any edit to the report after signing,
+/// including whitespace-insensitive changes to values or keys, makes verification fail.
+pub fn verify_report(json: &str, key: &VerifyingKey) -> anyhow::Result<()> {
+    let mut value: Value = serde_json::from_str(json).context("report is not valid JSON")?;
+    let signature = value.as_object_mut().and_then(|o| o.remove("signature")).ok_or_else(|| anyhow!("report has no signature"))?;
+    let signature: ReportSignature = serde_json::from_value(signature).context("malformed signature field")?;
+    if signature.algorithm != ALGORITHM {
+        bail!("unsupported signature algorithm '{}'", signature.algorithm);
+    }
+    if signature.public_key != BASE64.encode(key.as_bytes()) {
+        bail!("report was signed with a different key ({})", signature.public_key);
+    }
+    let bytes: [u8; 64] = BASE64
+        .decode(&signature.value)
+        .context("signature is not base64")?
+        .try_into()
+        .map_err(|_| anyhow!("signature must be 64 bytes"))?;
+    key.verify(canonical_json(&value).as_bytes(), &Signature::from_bytes(&bytes))
+        .map_err(|_| anyhow!("signature does not match report contents"))
+}
+
+/// Compact JSON with object keys sorted recursively, independent of how the report was formatted on disk.
+fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{}:{}", serde_json::to_string(k).unwrap(), canonical_json(&map[k])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::sample_report;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    #[test]
+    fn signed_report_verifies_after_write_and_read() {
+        let mut report = sample_report();
+        sign_report(&mut report, &key());
+        let json = serde_json::to_string_pretty(&report).unwrap();
+        assert!(json.contains("11.592373816040913"));
+        verify_report(&json, &key().verifying_key()).unwrap();
+    }
+
+    #[test]
+    fn edited_report_fails_verification() {
+        let mut report = sample_report();
+        sign_report(&mut report, &key());
+        let json = serde_json::to_string(&report).unwrap().replace("\"FAIL\"", "\"PASS\"");
+        let err = verify_report(&json, &key().verifying_key()).unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+    }
+
+    #[test]
+    fn wrong_key_is_reported() {
+        let mut report = sample_report();
+        sign_report(&mut report, &key());
+        let json = serde_json::to_string(&report).unwrap();
+        let other = SigningKey::from_bytes(&[8; 32]).verifying_key();
+        assert!(verify_report(&json, &other).unwrap_err().to_string().contains("different key"));
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys_recursively() {
+        let value: Value = serde_json::from_str(r#"{"b": [ {"y": 1, "x": 2.5} ], "a": "s"}"#).unwrap();
+        assert_eq!(canonical_json(&value), r#"{"a":"s","b":[{"x":2.5,"y":1}]}"#);
+    }
+}
@@ -0,0 +1,152 @@
+//! `--trace`: logs every file read, existence probe, system call measurement, command and timeout to stderr as it
+//! happens. Commands are traced by `exec`. With `--bundle` the same reads and command outputs are also kept, see
+//! [`capture`].
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Command output beyond this many characters is cut in the trace.
+const MAX_OUTPUT: usize = 200;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static START: OnceLock<Instant> = OnceLock::new();
+static CAPTURED: Mutex<Option<Vec<Capture>>> = Mutex::new(None);
+
+/// A file read or command output kept for the evidence bundle.
+#[derive(Debug, Clone)]
+pub struct Capture {
+    /// Host path, or the command line for command output
+    pub source: String,
+    /// `file`, `stat`, `measurement`, `stdout` or `stderr`
+    pub kind: &'static str,
+    /// The error message when the file could not be read
+    pub content: Result<Vec<u8>, String>,
+    pub at: SystemTime,
+}
+
+pub fn enable() {
+    START.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Logs one event; `detail` is only formatted when tracing is on.
+pub fn event(kind: &str, detail: impl FnOnce() -> String) {
+    if !enabled() { return; }
+    let elapsed = START.get().map(Instant::elapsed).unwrap_or_default();
+    eprintln!("[trace {:>8.3}s] {:<7} {}", elapsed.as_secs_f64(), kind, detail());
+}
+
+/// Starts keeping every file read and command output from now on.
+pub fn capture() {
+    *CAPTURED.lock().unwrap() = Some(Vec::new());
+}
+
+/// Everything kept since [`capture`], in order.
+pub fn captured() -> Vec<Capture> {
+    CAPTURED.lock().unwrap().take().unwrap_or_default()
+}
+
+pub fn record(source: &str, kind: &'static str, content: Result<&[u8], String>) {
+    if let Some(captured) = CAPTURED.lock().unwrap().as_mut() {
+        captured.push(Capture { source: source.to_string(), kind, content: content.map(<[u8]>::to_vec), at: SystemTime::now() });
+    }
+}
+
+/// `fs::read_to_string` with a trace event.
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    let start = Instant::now();
+    let result = fs::read_to_string(path);
+    event("read", || match &result {
+        Ok(content) => format!("{} ({} bytes, {})", path.display(), content.len(), duration(start.elapsed())),
+        Err(e) => format!("{} failed: {}", path.display(), e),
+    });
+    record(&path.display().to_string(), "file", result.as_ref().map(|c| c.as_bytes()).map_err(|e| e.to_string()));
+    result
+}
+
+/// `Path::exists` with a trace event; the bundle keeps the file type, mode and size, or why it is missing.
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    let result = fs::metadata(path);
+    event("stat", || match &result {
+        Ok(meta) => format!("{} exists ({} bytes)", path.display(), meta.len()),
+        Err(e) => format!("{} missing: {}", path.display(), e),
+    });
+    let described = result.as_ref().map(describe).map_err(|e| e.to_string());
+    record(&path.display().to_string(), "stat", described.as_ref().map(|d| d.as_bytes()).map_err(Clone::clone));
+    result.is_ok()
+}
+
+/// A value read through a system call rather than a file (statvfs, sched_getaffinity), e.g. `source` `statvfs /`
+/// with `value` `total_bytes=... available_bytes=...`; kept in the bundle like a file read.
+pub fn measured(source: &str, value: impl FnOnce() -> String) {
+    if !enabled() && CAPTURED.lock().unwrap().is_none() { return; }
+    let value = value();
+    event("measure", || format!("{}: {}", source, value));
+    record(source, "measurement", Ok(format!("{}\n", value).as_bytes()));
+}
+
+fn describe(meta: &fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    let kind = if meta.is_dir() { "directory" } else if meta.is_file() { "file" } else { "other" };
+    format!("{} mode={:04o} uid={} gid={} size={}\n", kind, meta.mode() & 0o7777, meta.uid(), meta.gid(), meta.len())
+}
+
+pub fn command_line(command: &Command) -> String {
+    let mut parts = vec![command.get_program().to_string_lossy().into_owned()];
+    parts.extend(command.get_args().map(|a| a.to_string_lossy().into_owned()));
+    parts.join(" ")
+}
+
+pub fn duration(d: Duration) -> String {
+    if d < Duration::from_millis(1) { format!("{}µs", d.as_micros()) } else { format!("{}ms", d.as_millis()) }
+}
+
+/// Trims `text` to the first characters for a one-line trace event.
+pub fn truncate(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_OUTPUT { return text.to_string(); }
+    let cut: String = text.chars().take(MAX_OUTPUT).collect();
+    format!("{}... ({} bytes)", cut, text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_output_is_cut_and_durations_pick_a_unit() {
+        assert_eq!(truncate("  short \n"), "short");
+        let long = "x".repeat(MAX_OUTPUT + 50);
+        assert_eq!(truncate(&long), format!("{}... ({} bytes)", "x".repeat(MAX_OUTPUT), long.len()));
+        assert_eq!(duration(Duration::from_micros(250)), "250µs");
+        assert_eq!(duration(Duration::from_millis(42)), "42ms");
+    }
+
+    #[test]
+    fn probes_and_commands_behave_like_std() {
+        assert!(exists("/"));
+        assert!(!exists("/nonexistent/vps-audit"));
+        assert_eq!(command_line(Command::new("sshd").arg("-T")), "sshd -T");
+    }
+
+    #[test]
+    fn existence_probes_are_captured() {
+        capture();
+        assert!(exists("/"));
+        assert!(!exists("/nonexistent/vps-audit"));
+        let captured = captured();
+        let probe = |source: &str| captured.iter().find(|c| c.kind == "stat" && c.source == source).expect("probe captured");
+        assert!(probe("/").content.as_ref().is_ok_and(|c| c.starts_with(b"directory ")));
+        assert!(probe("/nonexistent/vps-audit").content.is_err());
+    }
+}